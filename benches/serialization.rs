@@ -0,0 +1,49 @@
+//! Compares the JSON and bincode encodings of a game in size and speed.
+//!
+//! Run with `cargo bench --bench serialization`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use first_to_five::persistence::{load, save, Format};
+use first_to_five::rules::{GameArea, Player};
+
+/// A long game without a winner: Cross and Naught fill alternate rows,
+/// leaving gaps so that no line gets long enough to win.
+fn long_game() -> GameArea {
+  let mut area = GameArea::default();
+  for y in 0..20 {
+    for x in 0..20 {
+      if x % 5 == 4 {
+        continue;
+      }
+      area
+        .play(Player::Cross, x, y * 2)
+        .expect("The plays of the benchmark game should be valid");
+      area
+        .play(Player::Naught, x, y * 2 + 1)
+        .expect("The plays of the benchmark game should be valid");
+    }
+  }
+  area
+}
+
+fn serialization(c: &mut Criterion) {
+  let area = long_game();
+  for &format in &[Format::Json, Format::Bincode] {
+    let bytes = save(&area, format);
+    println!(
+      "{:?}: {} moves in {} bytes",
+      format,
+      area.moves().count(),
+      bytes.len()
+    );
+
+    c.bench_function(&format!("save {:?}", format), |b| {
+      b.iter(|| save(black_box(&area), format))
+    });
+    c.bench_function(&format!("load {:?}", format), |b| {
+      b.iter(|| load(black_box(&bytes), format).unwrap())
+    });
+  }
+}
+
+criterion_group!(benches, serialization);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+#![no_main]
+use first_to_five::protobuf::{decode_game, decode_message, encode_game, encode_message};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+  if let Ok(area) = decode_game(bytes) {
+    let encoded = encode_game(&area).expect("Decoded coordinates fit in 64 bits");
+    let decoded = decode_game(&encoded).expect("Encoded game should decode");
+    assert_eq!(decoded.to_json(), area.to_json());
+  }
+
+  if let Ok(message) = decode_message(bytes) {
+    let encoded = encode_message(&message).expect("Decoded coordinates fit in 64 bits");
+    assert_eq!(decode_message(&encoded).unwrap(), message);
+  }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use first_to_five::rules::GameArea;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|json: &str| {
+  if let Ok(area) = GameArea::from_json(json) {
+    // Whatever was accepted must survive a round trip unchanged
+    let exported = area.to_json();
+    let reimported = GameArea::from_json(&exported).expect("Exported JSON should import");
+    assert_eq!(reimported.to_json(), exported);
+    assert_eq!(reimported.to_string(), area.to_string());
+  }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+use first_to_five::rules::GameArea;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+  if let Ok(area) = GameArea::from_move_list(text) {
+    // Whatever was accepted must survive a round trip unchanged
+    let moves = area.to_move_list();
+    let reparsed = GameArea::from_move_list(&moves).expect("Listed moves should parse");
+    assert_eq!(reparsed.to_move_list(), moves);
+  }
+
+  if let Ok(area) = GameArea::from_template(text) {
+    let template = area.to_template();
+    let reparsed = GameArea::from_template(&template).expect("Drawn template should parse");
+    assert_eq!(reparsed.to_template(), template);
+  }
+
+  // Telling the notations apart mustn't panic either
+  let _ = GameArea::parse_position(text);
+});
@@ -0,0 +1,64 @@
+#![no_main]
+use first_to_five::rules::{GameArea, Player, MAX_COORDINATE, MIN_COORDINATE, WINNING_LENGTH};
+use libfuzzer_sys::fuzz_target;
+
+// The plays are small steps from an origin anywhere in the coordinates, so
+// that they are close enough together to form lines, even at the ends of
+// the range.
+fuzz_target!(|input: ((i128, i128), Vec<(bool, i8, i8)>)| {
+  let ((origin_x, origin_y), plays) = input;
+  let mut area = GameArea::default();
+  for (is_cross, dx, dy) in plays {
+    let player = if is_cross { Player::Cross } else { Player::Naught };
+    let (x, y) = (
+      origin_x.saturating_add(dx as i128),
+      origin_y.saturating_add(dy as i128),
+    );
+    let was_valid = area.validate_play(player, x, y).is_ok();
+    let played = area.play(player, x, y).is_ok();
+    assert_eq!(was_valid, played);
+    let bounds = MIN_COORDINATE..=MAX_COORDINATE;
+    if !bounds.contains(&x) || !bounds.contains(&y) {
+      assert!(!played);
+    }
+    if played {
+      assert_invariants(&area, (x, y));
+    }
+  }
+});
+
+fn assert_invariants(area: &GameArea, (last_x, last_y): (i128, i128)) {
+  if let Err(violations) = area.check_consistency() {
+    panic!("Inconsistent game area: {:?}", violations);
+  }
+
+  let moves: Vec<_> = area.moves().collect();
+
+  // The bounding box is exactly as large as needed to hold every play
+  let (left, top) = area.origin();
+  let right = left + area.width() as i128;
+  let bottom = top + area.height() as i128;
+  for play in &moves {
+    assert!(left <= play.x() && play.x() < right);
+    assert!(top <= play.y() && play.y() < bottom);
+  }
+  assert!(moves.iter().any(|play| play.x() == left));
+  assert!(moves.iter().any(|play| play.x() == right - 1));
+  assert!(moves.iter().any(|play| play.y() == top));
+  assert!(moves.iter().any(|play| play.y() == bottom - 1));
+
+  // Players take turns
+  for pair in moves.windows(2) {
+    assert_ne!(pair[0].player(), pair[1].player());
+  }
+
+  // A winner is declared exactly when the last play made a line long enough
+  let line = area.longest_consecutive_line(last_x, last_y).unwrap();
+  let last_player = moves.last().unwrap().player();
+  assert!(line.iter().all(|play| play.player() == last_player));
+  if line.len() >= WINNING_LENGTH as usize {
+    assert_eq!(area.winner(), Some(last_player));
+  } else {
+    assert_eq!(area.winner(), None);
+  }
+}
@@ -0,0 +1,104 @@
+#ifndef FIRST_TO_FIVE_H
+#define FIRST_TO_FIVE_H
+
+/* Generated with cbindgen from src/ffi/mod.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The outcome of trying to mark a play
+typedef enum FtfMarkResult {
+  FTF_MARK_RESULT_OK = 0,
+  FTF_MARK_RESULT_WINNER_ALREADY_SELECTED = 1,
+  FTF_MARK_RESULT_NOT_PLAYERS_TURN = 2,
+  FTF_MARK_RESULT_ALREADY_MARKED = 3,
+  // The area pointer was null or the player wasn't `FtfPlayer::Naught` or
+  // `FtfPlayer::Cross`
+  FTF_MARK_RESULT_INVALID_ARGUMENT = 4,
+  // The library ran into a bug and the play may have been marked only
+  // partly, so the area shouldn't be played on anymore
+  FTF_MARK_RESULT_INTERNAL_ERROR = 5,
+} FtfMarkResult;
+
+// A player, or the lack of one
+typedef enum FtfPlayer {
+  FTF_PLAYER_NONE = 0,
+  FTF_PLAYER_NAUGHT = 1,
+  FTF_PLAYER_CROSS = 2,
+} FtfPlayer;
+
+typedef struct FtfGameArea FtfGameArea;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a new empty game area, or returns null if it couldn't be created.
+// Free it with `ftf_game_area_free`.
+FtfGameArea *ftf_game_area_new(void);
+
+// Frees a game area created with `ftf_game_area_new`. Passing null is a
+// no-op.
+//
+// # Safety
+//
+// `area` must be null or a pointer from `ftf_game_area_new` which hasn't
+// been freed yet.
+void ftf_game_area_free(FtfGameArea *area);
+
+// Marks a new play, checking first that the rules allow it. The player is
+// one of the `FtfPlayer` values, taken as an integer as C allows passing
+// any.
+//
+// # Safety
+//
+// `area` must be null or a valid pointer from `ftf_game_area_new`.
+FtfMarkResult ftf_game_area_mark(FtfGameArea *area, int player, int64_t x, int64_t y);
+
+// The winner of the game, or `FTF_PLAYER_NONE` if nobody has won yet
+//
+// # Safety
+//
+// `area` must be null or a valid pointer from `ftf_game_area_new`.
+FtfPlayer ftf_game_area_winner(const FtfGameArea *area);
+
+// The player whose turn it is, or `FTF_PLAYER_NONE` before the first play
+//
+// # Safety
+//
+// `area` must be null or a valid pointer from `ftf_game_area_new`.
+FtfPlayer ftf_game_area_next_player(const FtfGameArea *area);
+
+// The whole game in the JSON interchange format, as a NUL-terminated UTF-8
+// string. Free it with `ftf_string_free`. Returns null if `area` is null or
+// the game couldn't be serialized.
+//
+// # Safety
+//
+// `area` must be null or a valid pointer from `ftf_game_area_new`.
+char *ftf_game_area_serialize(const FtfGameArea *area);
+
+// Creates a game area from JSON produced by `ftf_game_area_serialize`.
+// Returns null if the JSON isn't a valid game. Free the area with
+// `ftf_game_area_free`.
+//
+// # Safety
+//
+// `json` must be null or a valid NUL-terminated string.
+FtfGameArea *ftf_game_area_deserialize(const char *json);
+
+// Frees a string returned by this library. Passing null is a no-op.
+//
+// # Safety
+//
+// `string` must be null or a string returned by this library which hasn't
+// been freed yet.
+void ftf_string_free(char *string);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif // FIRST_TO_FIVE_H
@@ -0,0 +1,155 @@
+# English texts of the game. Every other locale falls back to these for the
+# messages it doesn't translate.
+
+## Players
+
+default-cross-name = Cross
+default-naught-name = Naught
+crosses = Crosses
+naughts = Naughts
+named-player = { $name } ({ $symbol })
+ask-cross-name = Name of the player playing crosses
+ask-naught-name = Name of the player playing naughts
+
+## The game window
+
+title-to-play = first-to-five: { $player } to play
+title-won = first-to-five: { $player } won
+title-out-of-time = first-to-five: { $player } ran out of time
+title-pasted-position = first-to-five: pasted position
+player-won = { $player } won!
+player-out-of-time = { $player } ran out of time!
+title-notice = first-to-five: { $notice }
+illegal-play = Can't play there: { $error }
+game-ended = Game has ended!
+
+## Announcements for screen readers
+
+announce-play = { $player } played at { $x }, { $y }
+announce-turn = { $player } to play
+announce-focus-empty = { $x }, { $y }: empty
+announce-focus-taken = { $x }, { $y }: { $player }
+
+## Themes
+
+theme-load-failed = Couldn't load { $path }: { $error }
+theme-read-failed = Couldn't read { $path }: { $error }
+
+## Reloading changed settings
+
+theme-reloaded = Loaded the changed theme
+settings-reloaded = Loaded the changed settings
+reload-failed = Kept the old settings: { $error }
+
+## Bug reports
+
+report-written = Wrote a bug report to { $path }
+report-failed = Couldn't write the bug report: { $error }
+
+## Pasting positions
+
+clipboard-unreadable = Couldn't read the clipboard: { $error }
+paste-failed = Couldn't paste the position: { $error }
+pasted-position =
+    Pasted position:
+    { $position }
+    Press Enter to load it or Backspace to go back to the current game
+pasted-position-loaded =
+    Loaded the pasted position:
+    { $position }
+pasted-position-cancelled = Cancelled loading the pasted position
+
+## Copying positions
+
+clipboard-unwritable = Couldn't copy to the clipboard: { $error }
+copied-position =
+    Copied the position:
+    { $position }
+
+## Recording the game window
+
+recording-failed = Couldn't record the game: { $error }
+replay-usage = Usage: first-to-five replay FILE
+replay-unreadable = Couldn't read the recording: { $error }
+replay-finished = The recording has been played back, the game goes on from here
+
+## The move journal
+
+journal-failed = Couldn't write the move journal: { $error }
+journal-play-failed = Couldn't write the play into the move journal, so it wasn't made: { $error }
+journal-unreadable = Couldn't read the move journal: { $error }
+journal-recovered =
+    Recovered the game which was going on when the game last closed:
+    { $position }
+
+## Scenarios
+
+scenario-usage = Give the scenario as a file or the name of one in the scenarios directory, like --scenario open-four
+
+## Random numbers
+
+seed = Random seed { $seed }, run with --seed { $seed } to repeat this
+invalid-seed = Give the seed as a whole number, like --seed 42
+
+## Profiles and achievements
+
+profiles-no-place = Couldn't find a place for the profiles, playing without them
+profiles-unreadable = { $error }, starting with no profiles
+profile-created = Creating a new profile for { $name }
+seating = { $naught } ({ $naught_rating }) plays naughts and { $cross } ({ $cross_rating }) plays crosses
+seating-unrated = { $naught } plays naughts and { $cross } plays crosses
+achievement-unlocked = { $name } unlocked an achievement: { $achievement }
+achievement-first-win = First win
+achievement-double-threat-win = Double trouble: win with a double threat
+achievement-quick-win = Quick win: win in under { $plays } plays
+achievement-beat-hardest-ai = Beat the hardest computer opponent
+achievement-winning-streak = On a roll: win { $games } games in a row
+
+## Time controls
+
+invalid-time-control = Give the time control as minutes and increment seconds, like one of { $presets }
+
+## The game database
+
+database-no-place = Couldn't find a place for the game database
+game-saved = Saved the game as number { $id } in { $path }
+game-not-saved = Couldn't save the game: { $error }
+
+## Statistics
+
+stats-usage = Usage: first-to-five stats NAME
+stats-need-database = Statistics need the game database, build with the database feature
+stats-title = Statistics of { $name }
+stats-naughts = As naughts: { $games } games, { $wins } won, { $losses } lost ({ $win_rate } % won)
+stats-crosses = As crosses: { $games } games, { $wins } won, { $losses } lost ({ $win_rate } % won)
+stats-naughts-no-games = As naughts: no games
+stats-crosses-no-games = As crosses: no games
+stats-average-length = Average game length: { $plays } plays
+stats-opening-square = { $square } ({ $times } times)
+stats-opening-squares = Favourite opening squares: { $squares }
+heatmap-title = Where { $name } plays in the opening, relative to the first play
+heatmap-title-everyone = Where the openings are played, relative to the first play
+heatmap-no-games = No games have been recorded yet
+heatmap-square = { $square }: { $plays } plays, { $win_rate } % won
+history-usage = Usage: first-to-five history [NAME] [--against NAME] [--won | --lost | --unfinished] [--days DAYS] [--limit COUNT]
+history-no-games = No recorded games match
+history-game = #{ $id } { $naught } (O) vs { $cross } (X): { $outcome }, { $plays } plays, { $days } days ago
+history-won = { $name } won
+history-no-winner = no winner
+
+## Assessing positions
+
+assess-usage = Usage: first-to-five assess POSITION, like first-to-five assess x0,0 o1,1 x1,0
+assess-outcomes = Out of { $playouts } random playouts crosses won { $cross } %, naughts won { $naught } % and { $unfinished } went on too long
+assess-move = { $square }: { $win_rate } % won
+
+## Tournaments
+
+tournament-usage = Usage: first-to-five tournament [new round-robin|knockout NAME... | standings]
+tournament-invalid-format = The format is either round-robin or knockout
+tournament-exists = There already is a tournament in { $path }, remove it to start a new one
+tournament-round = Round { $round }: { $cross } (x) against { $naught } (o)
+tournament-next-game = Next game: { $cross } (x) against { $naught } (o)
+tournament-standings = Standings:
+tournament-standing = { $place }. { $name } { $points } points, { $wins } won, { $losses } lost, { $unfinished } unfinished
+tournament-winner = { $name } won the tournament!
@@ -0,0 +1,154 @@
+# Pelin suomenkieliset tekstit
+
+## Pelaajat
+
+default-cross-name = Risti
+default-naught-name = Nolla
+crosses = Ristit
+naughts = Nollat
+named-player = { $name } ({ $symbol })
+ask-cross-name = Risteillä pelaavan nimi
+ask-naught-name = Nollilla pelaavan nimi
+
+## Peli-ikkuna
+
+title-to-play = first-to-five: { $player } vuorossa
+title-won = first-to-five: { $player } voitti
+title-out-of-time = first-to-five: aika loppui, { $player } hävisi
+title-pasted-position = first-to-five: liitetty asema
+player-won = { $player } voitti!
+player-out-of-time = Aika loppui, { $player } hävisi!
+title-notice = first-to-five: { $notice }
+illegal-play = Siihen ei voi pelata: { $error }
+game-ended = Peli päättyi!
+
+## Ruudunlukijan ilmoitukset
+
+announce-play = { $player } pelasi ruutuun { $x }, { $y }
+announce-turn = { $player } vuorossa
+announce-focus-empty = { $x }, { $y }: tyhjä
+announce-focus-taken = { $x }, { $y }: { $player }
+
+## Teemat
+
+theme-load-failed = Tiedostoa { $path } ei voitu ladata: { $error }
+theme-read-failed = Tiedostoa { $path } ei voitu lukea: { $error }
+
+## Muuttuneiden asetusten lataaminen
+
+theme-reloaded = Muuttunut teema ladattiin
+settings-reloaded = Muuttuneet asetukset ladattiin
+reload-failed = Vanhat asetukset pidettiin: { $error }
+
+## Virheraportit
+
+report-written = Virheraportti kirjoitettiin tiedostoon { $path }
+report-failed = Virheraporttia ei voitu kirjoittaa: { $error }
+
+## Asemien liittäminen
+
+clipboard-unreadable = Leikepöytää ei voitu lukea: { $error }
+paste-failed = Asemaa ei voitu liittää: { $error }
+pasted-position =
+    Liitetty asema:
+    { $position }
+    Lataa asema painamalla Enter tai palaa peliin painamalla Backspace
+pasted-position-loaded =
+    Liitetty asema ladattiin:
+    { $position }
+pasted-position-cancelled = Liitetyn aseman lataaminen peruttiin
+
+## Asemien kopioiminen
+
+clipboard-unwritable = Leikepöydälle ei voitu kopioida: { $error }
+copied-position =
+    Kopioitiin asema:
+    { $position }
+
+## Peli-ikkunan tallentaminen
+
+recording-failed = Peliä ei voitu tallentaa tiedostoon: { $error }
+replay-usage = Käyttö: first-to-five replay TIEDOSTO
+replay-unreadable = Tallennetta ei voitu lukea: { $error }
+replay-finished = Tallenne toistettiin, peli jatkuu tästä
+
+## Siirtopäiväkirja
+
+journal-failed = Siirtopäiväkirjaa ei voitu kirjoittaa: { $error }
+journal-play-failed = Siirtoa ei voitu kirjoittaa siirtopäiväkirjaan, joten sitä ei tehty: { $error }
+journal-unreadable = Siirtopäiväkirjaa ei voitu lukea: { $error }
+journal-recovered =
+    Palautettiin peli, joka oli kesken, kun peli viimeksi sulkeutui:
+    { $position }
+
+## Skenaariot
+
+scenario-usage = Anna skenaario tiedostona tai skenaariohakemistossa olevan nimellä, esimerkiksi --scenario open-four
+
+## Satunnaisluvut
+
+seed = Satunnaissiemen { $seed }, toista tämä ajamalla --seed { $seed }
+invalid-seed = Anna siemen kokonaislukuna, esimerkiksi --seed 42
+
+## Profiilit ja saavutukset
+
+profiles-no-place = Profiileille ei löytynyt paikkaa, pelataan ilman niitä
+profiles-unreadable = { $error }, aloitetaan ilman profiileja
+profile-created = Luodaan uusi profiili pelaajalle { $name }
+seating = { $naught } ({ $naught_rating }) pelaa nollilla ja { $cross } ({ $cross_rating }) risteillä
+seating-unrated = { $naught } pelaa nollilla ja { $cross } risteillä
+achievement-unlocked = { $name } ansaitsi saavutuksen: { $achievement }
+achievement-first-win = Ensimmäinen voitto
+achievement-double-threat-win = Tuplauhka: voita kaksoisuhalla
+achievement-quick-win = Pikavoitto: voita alle { $plays } siirrolla
+achievement-beat-hardest-ai = Voita vaikein tietokonevastustaja
+achievement-winning-streak = Putki päällä: voita { $games } peliä peräkkäin
+
+## Aikarajat
+
+invalid-time-control = Anna aikaraja minuutteina ja lisäsekunteina, esimerkiksi { $presets }
+
+## Pelitietokanta
+
+database-no-place = Pelitietokannalle ei löytynyt paikkaa
+game-saved = Peli tallennettiin numerolla { $id } tiedostoon { $path }
+game-not-saved = Peliä ei voitu tallentaa: { $error }
+
+## Tilastot
+
+stats-usage = Käyttö: first-to-five stats NIMI
+stats-need-database = Tilastot tarvitsevat pelitietokannan, käännä peli database-ominaisuuden kanssa
+stats-title = Pelaajan { $name } tilastot
+stats-naughts = Nollilla: pelejä { $games }, voittoja { $wins }, tappioita { $losses } ({ $win_rate } % voitettu)
+stats-crosses = Risteillä: pelejä { $games }, voittoja { $wins }, tappioita { $losses } ({ $win_rate } % voitettu)
+stats-naughts-no-games = Nollilla: ei pelejä
+stats-crosses-no-games = Risteillä: ei pelejä
+stats-average-length = Pelin keskimääräinen pituus: { $plays } siirtoa
+stats-opening-square = { $square } ({ $times } kertaa)
+stats-opening-squares = Suosituimmat aloitusruudut: { $squares }
+heatmap-title = Mihin { $name } pelaa avauksessa, suhteessa ensimmäiseen siirtoon
+heatmap-title-everyone = Mihin avauksissa pelataan, suhteessa ensimmäiseen siirtoon
+heatmap-no-games = Pelejä ei ole vielä tallennettu
+heatmap-square = { $square }: { $plays } siirtoa, { $win_rate } % voitettu
+history-usage = Käyttö: first-to-five history [NIMI] [--against NIMI] [--won | --lost | --unfinished] [--days PÄIVIÄ] [--limit MÄÄRÄ]
+history-no-games = Hakuun sopivia pelejä ei ole tallennettu
+history-game = #{ $id } { $naught } (O) vs { $cross } (X): { $outcome }, { $plays } siirtoa, { $days } päivää sitten
+history-won = { $name } voitti
+history-no-winner = ei voittajaa
+
+## Asemien arvioiminen
+
+assess-usage = Käyttö: first-to-five assess ASEMA, esimerkiksi first-to-five assess x0,0 o1,1 x1,0
+assess-outcomes = { $playouts } satunnaisesta pelistä ristit voittivat { $cross } %, nollat { $naught } % ja { $unfinished } venyi liian pitkäksi
+assess-move = { $square }: { $win_rate } % voitettu
+
+## Turnaukset
+
+tournament-usage = Käyttö: first-to-five tournament [new round-robin|knockout NIMI... | standings]
+tournament-invalid-format = Turnausmuoto on joko round-robin tai knockout
+tournament-exists = Tiedostossa { $path } on jo turnaus, poista se aloittaaksesi uuden
+tournament-round = Kierros { $round }: { $cross } (x) vastaan { $naught } (o)
+tournament-next-game = Seuraava peli: { $cross } (x) vastaan { $naught } (o)
+tournament-standings = Tilanne:
+tournament-standing = { $place }. { $name } { $points } pistettä, voittoja { $wins }, tappioita { $losses }, kesken { $unfinished }
+tournament-winner = { $name } voitti turnauksen!
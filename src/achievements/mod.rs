@@ -0,0 +1,200 @@
+//! Achievements which players unlock by playing.
+//!
+//! After every game, [`AchievementProgress::record_game`] looks at how the
+//! game went for one player and tells which achievements they unlocked with
+//! it. The progress is stored in the player's profile.
+use crate::rules::{GameArea, Player};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A player who wins by making fewer plays than this unlocks
+/// [`Achievement::QuickWin`]
+pub const QUICK_WIN_PLAYS: usize = 7;
+
+/// How many wins in a row unlock [`Achievement::WinningStreak`]
+pub const WINNING_STREAK: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+  /// Won a game
+  FirstWin,
+  /// Won a game after making two winning threats at once, so that the
+  /// opponent could only block one of them
+  DoubleThreatWin,
+  /// Won a game with fewer than [`QUICK_WIN_PLAYS`] plays
+  QuickWin,
+  /// Won against the hardest computer opponent
+  BeatHardestAi,
+  /// Won [`WINNING_STREAK`] games in a row
+  WinningStreak,
+}
+
+impl fmt::Display for Achievement {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Achievement::FirstWin => write!(f, "First win"),
+      Achievement::DoubleThreatWin => write!(f, "Double trouble: win with a double threat"),
+      Achievement::QuickWin => write!(f, "Quick win: win in under {} plays", QUICK_WIN_PLAYS),
+      Achievement::BeatHardestAi => write!(f, "Beat the hardest computer opponent"),
+      Achievement::WinningStreak => write!(f, "On a roll: win {} games in a row", WINNING_STREAK),
+    }
+  }
+}
+
+/// A finished game from the point of view of one player
+#[derive(Debug, Clone)]
+pub struct FinishedGame<'a> {
+  pub area: &'a GameArea,
+  /// Who won, with a line on the board or on time
+  pub winner: Option<Player>,
+  /// The symbol the player played with
+  pub player: Player,
+  /// Whether the opponent was the hardest computer opponent
+  pub opponent_is_hardest_ai: bool,
+}
+
+/// The achievements a player has unlocked, and what is needed to track the
+/// ones which take more than one game
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AchievementProgress {
+  pub unlocked: Vec<Achievement>,
+  /// Games won in a row so far
+  pub win_streak: u32,
+}
+
+impl AchievementProgress {
+  pub fn has(&self, achievement: Achievement) -> bool {
+    self.unlocked.contains(&achievement)
+  }
+
+  /// Updates the progress with a finished game, returning the achievements
+  /// which were unlocked with it
+  pub fn record_game(&mut self, game: &FinishedGame) -> Vec<Achievement> {
+    let won = game.winner == Some(game.player);
+    self.win_streak = if won { self.win_streak + 1 } else { 0 };
+
+    let mut earned = vec![];
+    if won {
+      earned.push(Achievement::FirstWin);
+      if won_with_double_threat(game.area) {
+        earned.push(Achievement::DoubleThreatWin);
+      }
+      let plays = game
+        .area
+        .moves()
+        .filter(|play| play.player() == game.player)
+        .count();
+      if plays < QUICK_WIN_PLAYS {
+        earned.push(Achievement::QuickWin);
+      }
+      if game.opponent_is_hardest_ai {
+        earned.push(Achievement::BeatHardestAi);
+      }
+      if self.win_streak >= WINNING_STREAK {
+        earned.push(Achievement::WinningStreak);
+      }
+    }
+
+    earned.retain(|&achievement| !self.has(achievement));
+    self.unlocked.extend(&earned);
+    earned
+  }
+}
+
+/// Whether the winner had two or more ways to win after their second to last
+/// play, so the opponent couldn't block them all
+fn won_with_double_threat(area: &GameArea) -> bool {
+  let winner = match area.winner() {
+    Some(winner) => winner,
+    None => return false,
+  };
+  let moves: Vec<_> = area.moves().copied().collect();
+  if moves.len() < 2 {
+    return false;
+  }
+
+  let mut before = GameArea::default();
+  for play in &moves[..moves.len() - 2] {
+    if before.mark(play.player(), play.x(), play.y()).is_err() {
+      return false;
+    }
+  }
+  before.winning_spots(winner).len() >= 2
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn finished(area: &GameArea, player: Player) -> FinishedGame<'_> {
+    FinishedGame {
+      area,
+      winner: area.winner(),
+      player,
+      opponent_is_hardest_ai: false,
+    }
+  }
+
+  #[test]
+  fn test_quick_first_win() {
+    let area = GameArea::from_move_list("x0,0 o-1,0 x1,0 o0,1 x2,0 o1,1 x3,0 o2,1 x4,0").unwrap();
+    let mut progress = AchievementProgress::default();
+    assert_eq!(
+      progress.record_game(&finished(&area, Player::Cross)),
+      vec![Achievement::FirstWin, Achievement::QuickWin]
+    );
+    // Achievements are only unlocked once
+    assert_eq!(
+      progress.record_game(&finished(&area, Player::Cross)),
+      vec![]
+    );
+    assert_eq!(progress.win_streak, 2);
+
+    assert_eq!(
+      progress.record_game(&finished(&area, Player::Naught)),
+      vec![]
+    );
+    assert_eq!(progress.win_streak, 0);
+  }
+
+  #[test]
+  fn test_win_on_time() {
+    let area = GameArea::from_move_list("x0,0 o1,1").unwrap();
+    let game = FinishedGame {
+      winner: Some(Player::Naught),
+      ..finished(&area, Player::Naught)
+    };
+    let mut progress = AchievementProgress::default();
+    assert!(progress.record_game(&game).contains(&Achievement::FirstWin));
+    assert_eq!(progress.win_streak, 1);
+  }
+
+  #[test]
+  fn test_double_threat_win() {
+    // Cross has an open four, so naught can only block one of its ends
+    let area = GameArea::from_move_list("x1,0 o1,1 x2,0 o2,1 x3,0 o3,-1 x4,0 o0,0 x5,0").unwrap();
+    let mut progress = AchievementProgress::default();
+    let unlocked = progress.record_game(&finished(&area, Player::Cross));
+    assert!(unlocked.contains(&Achievement::DoubleThreatWin));
+
+    let blocked_once =
+      GameArea::from_move_list("x0,0 o-1,0 x1,0 o0,1 x2,0 o1,1 x3,0 o2,1 x4,0").unwrap();
+    let unlocked =
+      AchievementProgress::default().record_game(&finished(&blocked_once, Player::Cross));
+    assert!(!unlocked.contains(&Achievement::DoubleThreatWin));
+  }
+
+  #[test]
+  fn test_winning_streak() {
+    let area = GameArea::from_move_list("x0,0 o-1,0 x1,0 o0,1 x2,0 o1,1 x3,0 o2,1 x4,0").unwrap();
+    let mut progress = AchievementProgress {
+      win_streak: WINNING_STREAK - 1,
+      ..AchievementProgress::default()
+    };
+    assert!(progress
+      .record_game(&finished(&area, Player::Cross))
+      .contains(&Achievement::WinningStreak));
+  }
+}
@@ -0,0 +1,88 @@
+//! Bundling what is needed to look into a problem into a single zip file,
+//! which can be attached to an issue.
+//!
+//! The bundle has the position both as a template and as a move list, the
+//! game as JSON, the lines the game printed lately, the settings, the
+//! versions, and the recording of the game when one is being made.
+use super::log;
+use first_to_five::rules::{GameArea, FORMAT_VERSION};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::result::ZipResult;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// What the game was doing when the problem was reported
+pub struct BugReport<'a> {
+  pub area: &'a GameArea,
+  /// The settings in use, and the files they were loaded from
+  pub settings: String,
+  /// The file the game is being recorded into
+  pub recording: Option<&'a Path>,
+}
+
+impl BugReport<'_> {
+  /// Writes the report into a new zip file in the directory, and returns
+  /// the path of the file
+  pub fn write(&self, directory: &Path) -> ZipResult<PathBuf> {
+    let seconds = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |elapsed| elapsed.as_secs());
+    let path = directory.join(format!("first-to-five-report-{}.zip", seconds));
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    let mut add = |name: &str, contents: &[u8]| -> ZipResult<()> {
+      zip.start_file(name, FileOptions::default())?;
+      zip.write_all(contents)?;
+      Ok(())
+    };
+    let position = format!(
+      "{}\n\n{}\n",
+      self.area.to_template(),
+      self.area.to_move_list()
+    );
+    add("position.txt", position.as_bytes())?;
+    add("game.json", self.area.to_json().as_bytes())?;
+    add("log.txt", log::recent().join("\n").as_bytes())?;
+    add("settings.txt", self.settings.as_bytes())?;
+    add("version.txt", version().as_bytes())?;
+    if let Some(recording) = self.recording {
+      add("recording.jsonl", &fs::read(recording)?)?;
+    }
+    zip.finish()?;
+    Ok(path)
+  }
+}
+
+/// The versions of the game and its file formats, and the platform it runs on
+fn version() -> String {
+  format!(
+    "first-to-five {}\nsaved game format {}\n{} {}\n",
+    env!("CARGO_PKG_VERSION"),
+    FORMAT_VERSION,
+    std::env::consts::OS,
+    std::env::consts::ARCH
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::env;
+
+  #[test]
+  fn test_write() {
+    let area = GameArea::from_move_list("x0,0 o1,1").unwrap();
+    log!("A line for the report");
+    let report = BugReport {
+      area: &area,
+      settings: "play_size: 80".to_string(),
+      recording: None,
+    };
+    let path = report.write(&env::temp_dir()).unwrap();
+    assert!(fs::metadata(&path).unwrap().len() > 0);
+    assert!(log::recent().contains(&"A line for the report".to_string()));
+    fs::remove_file(path).unwrap();
+  }
+}
@@ -0,0 +1,113 @@
+//! The shapes making up a frame of the board.
+//!
+//! The shapes only change when the position, the view or the window does, so
+//! they are worked out once into a list and the same list is drawn on every
+//! frame until then.
+use super::theme::{Background, Theme};
+use graphics::{clear, line_from_to, Context, Ellipse, Image, Rectangle};
+use opengl_graphics::{GlGraphics, Texture};
+
+/// An image of the theme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sprite {
+  Cross,
+  Naught,
+  Highlight,
+  /// A tile of the background texture
+  Background,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+  Clear([f32; 4]),
+  Line {
+    color: [f32; 4],
+    radius: f64,
+    from: [f64; 2],
+    to: [f64; 2],
+  },
+  Rectangle {
+    color: [f32; 4],
+    rect: [f64; 4],
+  },
+  /// The outline of a rectangle
+  Frame {
+    color: [f32; 4],
+    radius: f64,
+    rect: [f64; 4],
+  },
+  /// The outline of an ellipse
+  Ring {
+    color: [f32; 4],
+    radius: f64,
+    rect: [f64; 4],
+  },
+  /// An image of the theme stretched over the rectangle. Nothing is drawn if
+  /// the theme doesn't have the image.
+  Sprite {
+    sprite: Sprite,
+    rect: [f64; 4],
+  },
+}
+
+/// Everything the shapes depend on
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawKey {
+  /// Changes whenever the shown position does, and when the theme is loaded
+  /// again after its files change. A theme which fails to load keeps the
+  /// version, as the old theme stays, and the translated error is shown in
+  /// the window title instead.
+  pub version: u64,
+  pub view_origin: (i128, i128),
+  pub zoom: f64,
+  pub window_size: [f64; 2],
+  pub focus: Option<(i128, i128)>,
+}
+
+fn texture(theme: &Theme, sprite: Sprite) -> Option<&Texture> {
+  let sprites = theme.sprites.as_ref();
+  match sprite {
+    Sprite::Cross => sprites.map(|sprites| &sprites.cross),
+    Sprite::Naught => sprites.map(|sprites| &sprites.naught),
+    Sprite::Highlight => sprites.and_then(|sprites| sprites.highlight.as_ref()),
+    Sprite::Background => match &theme.background {
+      Background::Texture(texture) => Some(texture),
+      Background::Color(_) => None,
+    },
+  }
+}
+
+pub fn draw(shapes: &[Shape], theme: &Theme, c: &Context, gl: &mut GlGraphics) {
+  let transform = c.transform;
+  for shape in shapes {
+    match *shape {
+      Shape::Clear(color) => clear(color, gl),
+      Shape::Line {
+        color,
+        radius,
+        from,
+        to,
+      } => line_from_to(color, radius, from, to, transform, gl),
+      Shape::Rectangle { color, rect } => {
+        Rectangle::new(color).draw(rect, &c.draw_state, transform, gl)
+      }
+      Shape::Frame {
+        color,
+        radius,
+        rect,
+      } => Rectangle::new_border(color, radius).draw(rect, &c.draw_state, transform, gl),
+      Shape::Ring {
+        color,
+        radius,
+        rect,
+      } => Ellipse::new_border(color, radius).draw(rect, &c.draw_state, transform, gl),
+      Shape::Sprite { sprite, rect } => {
+        if let Some(texture) = texture(theme, sprite) {
+          Image::new()
+            .rect(rect)
+            .draw(texture, &c.draw_state, transform, gl);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,34 @@
+//! The lines the game has printed lately, kept for bug reports. Everything
+//! the game prints while it is running goes through [`log!`].
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// How many of the latest lines are kept
+const KEPT_LINES: usize = 500;
+
+thread_local! {
+  static RECENT: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Prints the line like `println!` does, and keeps it for a bug report
+macro_rules! log {
+  ($($arg:tt)*) => {
+    $crate::app::log::record(format!($($arg)*))
+  };
+}
+
+pub fn record(line: String) {
+  println!("{}", line);
+  RECENT.with(|recent| {
+    let mut recent = recent.borrow_mut();
+    if recent.len() == KEPT_LINES {
+      recent.pop_front();
+    }
+    recent.push_back(line);
+  });
+}
+
+/// The kept lines, the oldest first
+pub fn recent() -> Vec<String> {
+  RECENT.with(|recent| recent.borrow().iter().cloned().collect())
+}
@@ -1,28 +1,179 @@
-use crate::rules::{GameArea, Player};
+#[macro_use]
+pub mod log;
+mod bug_report;
+mod draw_list;
+pub mod recording;
+mod speech;
+mod theme;
+mod watch;
+
+use bug_report::BugReport;
+use draw_list::{DrawKey, Shape, Sprite};
+use first_to_five::persistence::journal::Journal;
+use first_to_five::profiles::{ProfileError, ProfileSettings, Profiles};
+use first_to_five::rules::{Clocks, GameArea, IllegalPlayError, Player};
 use opengl_graphics::GlGraphics;
-use piston::input::{GenericEvent, RenderArgs, UpdateArgs};
+use piston::input::{Button, GenericEvent, Key, MouseButton, RenderArgs, UpdateArgs};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use theme::{Background, Theme, TILE_PLAYS};
+use watch::Watcher;
+
+/// How much one step of the mouse wheel zooms
+const ZOOM_STEP: f64 = 1.25;
+const MIN_ZOOM: f64 = 0.02;
+const MAX_ZOOM: f64 = 4.0;
+/// Below this size, in logical pixels, plays are drawn as plain dots without
+/// the grid, as lines and margins would only blur into noise
+const DETAILED_PLAY_SIZE: f64 = 12.0;
+/// How long a notice stays in the window title, in seconds
+const NOTICE_SECONDS: f64 = 5.0;
 
+#[derive(Debug)]
 pub struct AppSettings {
   pub scale_factor: f64,
+  /// How large a single play is drawn, in logical pixels
+  pub play_size: f64,
+  /// Whether plays, turns and results are read aloud and printed for screen
+  /// readers
+  pub announce: bool,
+}
+
+/// The names of the players in a local game between two people
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerNames {
+  pub naught: String,
+  pub cross: String,
+}
+
+impl PlayerNames {
+  pub fn get(&self, player: Player) -> &str {
+    match player {
+      Player::Naught => &self.naught,
+      Player::Cross => &self.cross,
+    }
+  }
+}
+
+/// The profile whose settings the game follows, watched for changes
+struct WatchedProfile {
+  path: PathBuf,
+  name: String,
+  watcher: Watcher,
 }
 
 pub struct App<'a> {
-  gl: GlGraphics,          // OpenGL drawing backend.
-  game_area: &'a GameArea, // The game area we're running
+  gl: GlGraphics,              // OpenGL drawing backend.
+  game_area: &'a mut GameArea, // The game area we're running
   settings: AppSettings,
+  /// A position pasted from the clipboard, shown until it is either confirmed
+  /// or cancelled
+  pasted_area: Option<GameArea>,
+  /// Whether Ctrl or Cmd is held down, for the copy and paste shortcuts
+  modifier_held: bool,
+  /// Whether Shift is held down, to copy the move list instead
+  shift_held: bool,
+  /// Names of the players, if they gave them
+  names: Option<PlayerNames>,
+  /// Where the mouse cursor is in the window
+  cursor: [f64; 2],
+  /// The coordinates of the play drawn in the top left corner of the window.
+  /// The shown area is centered again when this is `None`.
+  view_origin: Option<(i128, i128)>,
+  /// How much the board has been zoomed from the size of a play in the
+  /// settings
+  zoom: f64,
+  /// The size of the window the last time it was drawn
+  window_size: [f64; 2],
+  /// The spot chosen with the arrow keys, once they have been used
+  focus: Option<(i128, i128)>,
+  /// The clocks of a timed game
+  clocks: Option<Clocks>,
+  /// How long the player in turn has been thinking
+  thinking_seconds: f64,
+  /// The player who lost by running out of time
+  out_of_time: Option<Player>,
+  /// How the board looks
+  theme: Theme,
+  theme_watcher: Watcher,
+  profile: Option<WatchedProfile>,
+  /// A message shown in the window title for a while, and the seconds it
+  /// has left
+  notice: Option<(String, f64)>,
+  /// Changes whenever the shown position does, so that the board is drawn
+  /// anew
+  version: u64,
+  /// The shapes drawn on the last frame, and what they were worked out from
+  draw_list: Option<(DrawKey, Vec<Shape>)>,
+  /// Where every play is written before it is made
+  journal: Option<&'a mut Journal>,
+  /// The file the game is being recorded into, to add to bug reports
+  recording: Option<PathBuf>,
 }
 
 impl<'a> App<'a> {
-  pub fn new(gl: GlGraphics, game_area: &'a mut GameArea, settings: AppSettings) -> App<'a> {
+  pub fn new(
+    gl: GlGraphics,
+    game_area: &'a mut GameArea,
+    settings: AppSettings,
+    names: Option<PlayerNames>,
+    clocks: Option<Clocks>,
+  ) -> App<'a> {
     let app = Self {
       gl,
       game_area,
       settings,
+      pasted_area: None,
+      modifier_held: false,
+      shift_held: false,
+      names,
+      cursor: [0.0, 0.0],
+      view_origin: None,
+      zoom: 1.0,
+      window_size: [0.0, 0.0],
+      focus: None,
+      clocks,
+      thinking_seconds: 0.0,
+      out_of_time: None,
+      theme: Theme::from_environment(),
+      theme_watcher: Watcher::new(Theme::files(&Theme::directory())),
+      profile: None,
+      notice: None,
+      version: 0,
+      draw_list: None,
+      journal: None,
+      recording: None,
     };
-    println!("Initialized App with game area:\n{}", app.game_area);
+    log!("Initialized App with game area:\n{}", app.game_area);
+    app.announce(&tr!(
+      "announce-turn",
+      player = app.player_name(app.player_in_turn())
+    ));
     app
   }
 
+  /// Writes every play into the journal before making it
+  pub fn set_journal(&mut self, journal: &'a mut Journal) {
+    self.journal = Some(journal);
+  }
+
+  /// Adds the recording in the file to bug reports
+  pub fn set_recording(&mut self, path: PathBuf) {
+    self.recording = Some(path);
+  }
+
+  /// Applies the settings of the profile again whenever the profiles file
+  /// changes
+  pub fn watch_profile(&mut self, path: PathBuf, name: String) {
+    self.profile = Some(WatchedProfile {
+      watcher: Watcher::new(vec![path.clone()]),
+      path,
+      name,
+    });
+  }
+
   pub fn event(&mut self, e: &impl GenericEvent) {
     if let Some(args) = e.render_args() {
       self.render(&args);
@@ -31,111 +182,769 @@ impl<'a> App<'a> {
     if let Some(args) = e.update_args() {
       self.update(&args);
     }
+
+    if let Some(cursor) = e.mouse_cursor_args() {
+      self.cursor = cursor;
+    }
+
+    if let Some([_, scroll]) = e.mouse_scroll_args() {
+      self.zoom(scroll);
+    }
+
+    match e.press_args() {
+      Some(Button::Keyboard(key)) => self.key_pressed(key),
+      Some(Button::Mouse(MouseButton::Left)) => self.clicked(),
+      _ => {}
+    }
+
+    if let Some(Button::Keyboard(key)) = e.release_args() {
+      if is_modifier(key) {
+        self.modifier_held = false;
+      }
+      if is_shift(key) {
+        self.shift_held = false;
+      }
+    }
+
+    // The keys may be let go while another window has the focus, and then
+    // there is no release event for them
+    if e.focus_args() == Some(false) {
+      self.modifier_held = false;
+      self.shift_held = false;
+    }
+  }
+
+  fn key_pressed(&mut self, key: Key) {
+    match key {
+      key if is_modifier(key) => self.modifier_held = true,
+      key if is_shift(key) => self.shift_held = true,
+      Key::C if self.modifier_held => self.copy(),
+      Key::V if self.modifier_held => self.paste(),
+      Key::F12 => self.report_problem(),
+      Key::Left => self.move_focus(-1, 0),
+      Key::Right => self.move_focus(1, 0),
+      Key::Up => self.move_focus(0, -1),
+      Key::Down => self.move_focus(0, 1),
+      Key::Space => self.play_at_focus(),
+      Key::Plus | Key::Equals | Key::NumPadPlus => self.zoom(1.0),
+      Key::Minus | Key::NumPadMinus => self.zoom(-1.0),
+      Key::Return => match self.pasted_area.take() {
+        Some(pasted_area) => {
+          if let Some(journal) = &mut self.journal {
+            if let Err(error) = journal.restart(&pasted_area) {
+              self.tell(&tr!("journal-failed", error = error.to_string()));
+              self.pasted_area = Some(pasted_area);
+              return;
+            }
+          }
+          *self.game_area = pasted_area;
+          self.view_origin = None;
+          self.version += 1;
+          log!(
+            "{}",
+            tr!(
+              "pasted-position-loaded",
+              position = self.game_area.to_string()
+            )
+          );
+        }
+        None => self.play_at_focus(),
+      },
+      Key::Backspace if self.pasted_area.is_some() => {
+        self.pasted_area = None;
+        self.view_origin = None;
+        self.version += 1;
+        log!("{}", tr!("pasted-position-cancelled"));
+      }
+      _ => {}
+    }
+  }
+
+  /// Marks a play for the player in turn where the mouse was clicked
+  fn clicked(&mut self) {
+    let view_origin = match self.view_origin {
+      Some(view_origin) => view_origin,
+      None => return,
+    };
+    let play_size = self.play_size();
+    let x = view_origin.0 + (self.cursor[0] / play_size).floor() as i128;
+    let y = view_origin.1 + (self.cursor[1] / play_size).floor() as i128;
+    self.play(x, y);
+  }
+
+  /// Marks a play for the player in turn on the spot chosen with the arrow
+  /// keys
+  fn play_at_focus(&mut self) {
+    if let Some((x, y)) = self.focus {
+      self.play(x, y);
+    }
+  }
+
+  fn play(&mut self, x: i128, y: i128) {
+    if self.pasted_area.is_some() || self.out_of_time.is_some() {
+      return;
+    }
+    let player = self.player_in_turn();
+    let thinking_ms = self.thinking_ms();
+    let result = make_play(
+      self.game_area,
+      self.clocks.as_mut(),
+      self.journal.as_deref_mut(),
+      (player, x, y),
+      thinking_ms,
+    );
+    match result {
+      Ok(()) => {
+        self.version += 1;
+        self.thinking_seconds = 0.0;
+        self.announce(&tr!(
+          "announce-play",
+          player = self.player_name(player),
+          x = x.to_string(),
+          y = y.to_string()
+        ));
+        match self.game_area.winner() {
+          Some(winner) => {
+            self.tell(&tr!("player-won", player = self.player_name(winner)));
+            log!("{}", self.game_area);
+          }
+          None => self.announce(&tr!(
+            "announce-turn",
+            player = self.player_name(self.player_in_turn())
+          )),
+        }
+      }
+      Err(PlayError::Illegal(error)) => self.tell(&tr!("illegal-play", error = error.to_string())),
+      Err(PlayError::OutOfTime) => {
+        self.out_of_time = Some(player);
+        self.tell(&tr!(
+          "player-out-of-time",
+          player = self.player_name(player)
+        ));
+      }
+      Err(PlayError::Journal(error)) => {
+        self.tell(&tr!("journal-play-failed", error = error.to_string()))
+      }
+    }
+  }
+
+  /// Moves the spot chosen with the arrow keys, scrolling the view to keep it
+  /// shown. The spot starts from the latest play.
+  fn move_focus(&mut self, dx: i128, dy: i128) {
+    let (x, y) = match self.focus {
+      Some((x, y)) => (x + dx, y + dy),
+      None => self
+        .game_area
+        .moves()
+        .last()
+        .map_or((0, 0), |play| (play.x(), play.y())),
+    };
+    self.focus = Some((x, y));
+    let (width, height) = self.view_size();
+    if let Some((left, top)) = &mut self.view_origin {
+      *left = (*left).clamp(x - width + 1, x);
+      *top = (*top).clamp(y - height + 1, y);
+    }
+    let text = match self
+      .game_area
+      .moves()
+      .find(|play| (play.x(), play.y()) == (x, y))
+    {
+      Some(play) => tr!(
+        "announce-focus-taken",
+        x = x.to_string(),
+        y = y.to_string(),
+        player = self.player_name(play.player())
+      ),
+      None => tr!("announce-focus-empty", x = x.to_string(), y = y.to_string()),
+    };
+    self.announce(&text);
+  }
+
+  /// How large a play is drawn at the current zoom, in logical pixels
+  fn play_size(&self) -> f64 {
+    self.settings.play_size / self.settings.scale_factor * self.zoom
+  }
+
+  /// How many plays fit in the window horizontally and vertically
+  fn view_size(&self) -> (i128, i128) {
+    let play_size = self.play_size();
+    (
+      ((self.window_size[0] / play_size) as i128).max(1),
+      ((self.window_size[1] / play_size) as i128).max(1),
+    )
+  }
+
+  /// Zooms in, or out with negative steps, keeping the spot in the middle of
+  /// the window in place
+  fn zoom(&mut self, steps: f64) {
+    let (width, height) = self.view_size();
+    let center = self
+      .view_origin
+      .map(|(left, top)| (left + width / 2, top + height / 2));
+    self.zoom = (self.zoom * ZOOM_STEP.powf(steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+    if let Some((x, y)) = center {
+      let (width, height) = self.view_size();
+      self.view_origin = Some((x - width / 2, y - height / 2));
+    }
+  }
+
+  /// Prints the text, and reads it aloud when announcements are on
+  fn tell(&self, text: &str) {
+    log!("{}", text);
+    if self.settings.announce {
+      speech::speak(text);
+    }
+  }
+
+  /// Tells the text only when announcements are on, for what the players
+  /// otherwise see on the screen
+  fn announce(&self, text: &str) {
+    if self.settings.announce {
+      self.tell(text);
+    }
+  }
+
+  fn player_in_turn(&self) -> Player {
+    // Crosses start, like on paper
+    self.game_area.next_player().unwrap_or(Player::Cross)
+  }
+
+  fn thinking_ms(&self) -> u64 {
+    (self.thinking_seconds * 1000.0) as u64
+  }
+
+  fn player_name(&self, player: Player) -> String {
+    match &self.names {
+      Some(names) => tr!(
+        "named-player",
+        name = names.get(player),
+        symbol = symbol(player).to_string()
+      ),
+      None => match player {
+        Player::Naught => tr!("naughts"),
+        Player::Cross => tr!("crosses"),
+      },
+    }
+  }
+
+  /// Shows the text in the window title for a while, as nothing else draws
+  /// text
+  fn notify(&mut self, text: String) {
+    log!("{}", text);
+    self.notice = Some((text, NOTICE_SECONDS));
+  }
+
+  /// Loads the theme and the settings of the profile again if their files
+  /// have changed. Invalid changes are only told about, and what was loaded
+  /// before stays in use.
+  fn reload_changed(&mut self, seconds: f64) {
+    if self.theme_watcher.changed(seconds) {
+      match Theme::load(&Theme::directory()) {
+        Ok(theme) => {
+          self.theme = theme;
+          self.version += 1;
+          self.notify(tr!("theme-reloaded"));
+        }
+        Err(error) => self.notify(tr!("reload-failed", error = error)),
+      }
+    }
+    let profile = match &mut self.profile {
+      Some(profile) => profile,
+      None => return,
+    };
+    if !profile.watcher.changed(seconds) {
+      return;
+    }
+    let settings =
+      Profiles::load(&profile.path).and_then(|profiles| match profiles.get(&profile.name) {
+        Some(found) => Ok(found.settings.clone()),
+        None => Err(ProfileError::NotFound(profile.name.clone())),
+      });
+    match settings {
+      Ok(settings) => {
+        self.apply_settings(&settings);
+        self.notify(tr!("settings-reloaded"));
+      }
+      Err(error) => self.notify(tr!("reload-failed", error = error.to_string())),
+    }
+  }
+
+  fn apply_settings(&mut self, settings: &ProfileSettings) {
+    self.settings.play_size = settings.play_size;
+    self.settings.announce = settings.announce;
+    self.version += 1;
+  }
+
+  /// Who won the game, with a line on the board or on time
+  pub fn winner(&self) -> Option<Player> {
+    self
+      .game_area
+      .winner()
+      .or(self.out_of_time.map(|player| !player))
+  }
+
+  /// The window title, telling whose turn it is or who won
+  pub fn title(&self) -> String {
+    if let Some((notice, _)) = &self.notice {
+      return tr!("title-notice", notice = notice.as_str());
+    }
+    if self.pasted_area.is_some() {
+      return tr!("title-pasted-position");
+    }
+    if let Some(player) = self.out_of_time {
+      return tr!("title-out-of-time", player = self.player_name(player));
+    }
+    let mut title = match self.game_area.winner() {
+      Some(winner) => tr!("title-won", player = self.player_name(winner)),
+      None => tr!(
+        "title-to-play",
+        player = self.player_name(self.player_in_turn())
+      ),
+    };
+    if let Some(clocks) = &self.clocks {
+      for &player in &[Player::Cross, Player::Naught] {
+        if let Some(mut remaining_ms) = clocks.remaining_ms(player) {
+          if player == self.player_in_turn() && self.game_area.winner().is_none() {
+            remaining_ms = remaining_ms.saturating_sub(self.thinking_ms());
+          }
+          let seconds = remaining_ms.div_ceil(1000);
+          title.push_str(&format!(
+            " | {} {}:{:02}",
+            symbol(player),
+            seconds / 60,
+            seconds % 60
+          ));
+        }
+      }
+    }
+    title
+  }
+
+  /// Copies the shown position to the clipboard as a template, or as a move
+  /// list with Shift held, to share it in a bug report or chat
+  fn copy(&mut self) {
+    let area = self.pasted_area.as_ref().unwrap_or(&*self.game_area);
+    let text = if self.shift_held {
+      area.to_move_list()
+    } else {
+      area.to_template()
+    };
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.as_str())) {
+      Ok(()) => log!("{}", tr!("copied-position", position = text)),
+      Err(error) => log!("{}", tr!("clipboard-unwritable", error = error.to_string())),
+    }
+  }
+
+  /// Writes a bug report of the game into the current directory
+  fn report_problem(&mut self) {
+    let report = BugReport {
+      area: &*self.game_area,
+      settings: self.settings_report(),
+      recording: self.recording.as_deref(),
+    };
+    match report.write(Path::new(".")) {
+      Ok(path) => self.notify(tr!("report-written", path = path.display().to_string())),
+      Err(error) => self.notify(tr!("report-failed", error = error.to_string())),
+    }
+  }
+
+  /// The settings in use, followed by the theme file and the settings of
+  /// the profile they were loaded from. The rest of the profiles file has
+  /// the details of every other player, so it is left out.
+  fn settings_report(&self) -> String {
+    let mut report = format!("{:#?}\n", self.settings);
+    let theme = Theme::directory().join("theme.toml");
+    if let Ok(contents) = fs::read_to_string(&theme) {
+      report.push_str(&format!("\n# {}\n{}", theme.display(), contents));
+    }
+    if let Some(profile) = &self.profile {
+      report.push_str(&format!("\nprofile: {}\n", profile.name));
+      let settings = Profiles::load(&profile.path).ok().and_then(|profiles| {
+        let settings = &profiles.get(&profile.name)?.settings;
+        toml::to_string_pretty(settings).ok()
+      });
+      if let Some(settings) = settings {
+        let path = profile.path.display();
+        report.push_str(&format!(
+          "\n# Settings of the profile in {}\n{}",
+          path, settings
+        ));
+      }
+    }
+    report
+  }
+
+  /// Reads a position from the clipboard and shows it for confirmation
+  fn paste(&mut self) {
+    let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+      Ok(text) => text,
+      Err(error) => {
+        log!("{}", tr!("clipboard-unreadable", error = error.to_string()));
+        return;
+      }
+    };
+    match GameArea::parse_position(&text) {
+      Ok(pasted_area) => {
+        log!(
+          "{}",
+          tr!("pasted-position", position = pasted_area.to_string())
+        );
+        self.pasted_area = Some(pasted_area);
+        self.view_origin = None;
+        self.version += 1;
+      }
+      Err(error) => log!("{}", tr!("paste-failed", error = error.to_string())),
+    }
   }
 
   fn render(&mut self, args: &RenderArgs) {
-    use graphics::*;
+    self.window_size = args.window_size;
+    let play_size = self.play_size();
+    let area = self.pasted_area.as_ref().unwrap_or(&*self.game_area);
+    // Center the area in the window until it has been placed
+    let view_origin = *self.view_origin.get_or_insert_with(|| {
+      let (left, top) = area.origin();
+      let center_x = left + area.width() as i128 / 2;
+      let center_y = top + area.height() as i128 / 2;
+      (
+        center_x - (args.window_size[0] / play_size / 2.0) as i128,
+        center_y - (args.window_size[1] / play_size / 2.0) as i128,
+      )
+    });
+
+    let key = DrawKey {
+      version: self.version,
+      view_origin,
+      zoom: self.zoom,
+      window_size: args.window_size,
+      focus: self.focus,
+    };
+    if self.draw_list.as_ref().map(|(cached, _)| cached) != Some(&key) {
+      self.draw_list = Some((key, self.shapes(view_origin)));
+    }
+    let (theme, draw_list) = (&self.theme, &self.draw_list);
+    self.gl.draw(args.viewport(), |c, gl| {
+      if let Some((_, shapes)) = draw_list {
+        draw_list::draw(shapes, theme, &c, gl);
+      }
+    });
+  }
 
+  /// Works out what to draw with the given play in the top left corner
+  fn shapes(&self, (view_x, view_y): (i128, i128)) -> Vec<Shape> {
     const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
-    const GRAY: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.5, 1.0, 1.0];
     const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
-    const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+    const YELLOW: [f32; 4] = [1.0, 0.85, 0.0, 1.0];
+    const CROSS_DOT: [f32; 4] = [0.9, 0.3, 0.2, 1.0];
+    const NAUGHT_DOT: [f32; 4] = [0.3, 0.6, 1.0, 1.0];
 
-    let AppSettings { scale_factor } = self.settings;
-    let w_w = args.window_size[0];
-    let w_h = args.window_size[1];
+    let scale_factor = self.settings.scale_factor;
+    let [w_w, w_h] = self.window_size;
 
     // How large will we render a single play
-    let play_size = 80.0 / scale_factor;
+    let play_size = self.play_size();
+    let detailed = play_size >= DETAILED_PLAY_SIZE;
     // There should be a some margin between plays
-    let margin: f64 = 10.0 / scale_factor;
+    let margin: f64 = 10.0 / scale_factor * self.zoom.min(1.0);
     // The width of the lines for the plays
     let stroke: f64 = 2.0 / scale_factor;
     // The grid's line stroke width
     let grid_stroke: f64 = 1.0 / scale_factor;
 
-    let area_width = self.game_area.width() as usize;
-    let all_plays = self.game_area.all_plays();
-
-    self.gl.draw(args.viewport(), |c, gl| {
-      // Clear the screen.
-      clear(BLACK, gl);
-
-      let transform = c.transform;
+    // A pasted position is previewed in place of the game, with a blue frame
+    let (area, frame_color) = match &self.pasted_area {
+      Some(pasted_area) => (pasted_area, BLUE),
+      None => (&*self.game_area, RED),
+    };
+    let (view_width, view_height) = self.view_size();
+    let theme = &self.theme;
+    let mut shapes = vec![];
 
-      // Draw an empty rectangle around the play area
-      for (from, to) in &[
-        ([0.0, 0.0], [w_w, 0.0]),
-        ([w_w, 0.0], [w_w, w_h]),
-        ([w_w, w_h], [0.0, w_h]),
-        ([0.0, w_h], [0.0, 0.0]),
-      ] {
-        line_from_to(RED, 2.0, *from, *to, transform, gl);
+    // Cover the screen with the background, with the tiles of a texture
+    // fixed to the board so that they move along with it
+    match &theme.background {
+      Background::Color(color) => shapes.push(Shape::Clear(*color)),
+      // Zoomed out, the tiles would be too many and too small to make out
+      Background::Texture(_) if !detailed => shapes.push(Shape::Clear(BLACK)),
+      Background::Texture(_) => {
+        shapes.push(Shape::Clear(BLACK));
+        let tile_size = play_size * TILE_PLAYS as f64;
+        let first_x = (view_x.div_euclid(TILE_PLAYS) * TILE_PLAYS - view_x) as f64 * play_size;
+        let first_y = (view_y.div_euclid(TILE_PLAYS) * TILE_PLAYS - view_y) as f64 * play_size;
+        let mut tile_y = first_y;
+        while tile_y < w_h {
+          let mut tile_x = first_x;
+          while tile_x < w_w {
+            shapes.push(Shape::Sprite {
+              sprite: Sprite::Background,
+              rect: [tile_x, tile_y, tile_size, tile_size],
+            });
+            tile_x += tile_size;
+          }
+          tile_y += tile_size;
+        }
       }
+    }
+
+    // Draw an empty rectangle around the play area
+    for (from, to) in &[
+      ([0.0, 0.0], [w_w, 0.0]),
+      ([w_w, 0.0], [w_w, w_h]),
+      ([w_w, w_h], [0.0, w_h]),
+      ([0.0, w_h], [0.0, 0.0]),
+    ] {
+      shapes.push(Shape::Line {
+        color: frame_color,
+        radius: 2.0,
+        from: *from,
+        to: *to,
+      });
+    }
 
-      // Then draw the grid.
+    // Then draw the grid, unless zoomed so far out that the lines would
+    // cover the whole board.
+    if detailed {
       let horizontal_lines_count = (w_w / play_size).ceil() as u64;
       let vertical_lines_count = (w_h / play_size).ceil() as u64;
       for i in 1..(vertical_lines_count) {
         let y = (i as f64) * play_size;
-        line_from_to(GRAY, grid_stroke, [0.0, y], [w_w, y], transform, gl);
+        shapes.push(Shape::Line {
+          color: theme.grid_color,
+          radius: grid_stroke,
+          from: [0.0, y],
+          to: [w_w, y],
+        });
       }
       for i in 1..(horizontal_lines_count) {
         let x = (i as f64) * play_size;
-        line_from_to(GRAY, grid_stroke, [x, 0.0], [x, w_h], transform, gl);
-      }
-
-      for (i, maybe_player) in all_plays.iter().enumerate() {
-        let x = (i % area_width) as f64;
-        let y = (i / area_width) as f64;
-
-        let start_x = (play_size * x) + margin;
-        let start_y = (play_size * y) + margin;
-        let size = play_size - margin * 2.0;
-
-        match maybe_player {
-          Some(Player::Cross) => {
-            // Draw the cross
-            line_from_to(
-              WHITE,
-              stroke,
-              [start_x, start_y],
-              [start_x + size, start_y + size],
-              transform,
-              gl,
-            );
-            line_from_to(
-              WHITE,
-              stroke,
-              [start_x + size, start_y],
-              [start_x, start_y + size],
-              transform,
-              gl,
-            );
-          }
-          Some(Player::Naught) => {
-            ellipse(WHITE, [start_x, start_y, size, size], transform, gl);
-            ellipse(
-              BLACK,
-              [
-                start_x + (stroke * 2.0),
-                start_y + (stroke * 2.0),
-                size - (stroke * 4.0),
-                size - (stroke * 4.0),
-              ],
-              transform,
-              gl,
-            );
-          }
-          None => {
-            // Empty on purpose
-          }
+        shapes.push(Shape::Line {
+          color: theme.grid_color,
+          radius: grid_stroke,
+          from: [x, 0.0],
+          to: [x, w_h],
+        });
+      }
+    }
+
+    // Only the plays in the window are drawn, however large the board
+    let plays = area
+      .moves()
+      .map(|play| (play.player(), play.x() - view_x, play.y() - view_y))
+      .filter(|(_, x, y)| (0..=view_width).contains(x) && (0..=view_height).contains(y));
+    for (player, x, y) in plays {
+      let start_x = (play_size * x as f64) + margin;
+      let start_y = (play_size * y as f64) + margin;
+      let size = play_size - margin * 2.0;
+
+      if !detailed {
+        let color = match player {
+          Player::Cross => CROSS_DOT,
+          Player::Naught => NAUGHT_DOT,
+        };
+        let rect = [
+          play_size * x as f64,
+          play_size * y as f64,
+          play_size,
+          play_size,
+        ];
+        shapes.push(Shape::Rectangle { color, rect });
+        continue;
+      }
+
+      if theme.sprites.is_some() {
+        let sprite = match player {
+          Player::Cross => Sprite::Cross,
+          Player::Naught => Sprite::Naught,
+        };
+        let rect = [start_x, start_y, size, size];
+        shapes.push(Shape::Sprite { sprite, rect });
+        continue;
+      }
+
+      match player {
+        Player::Cross => {
+          // Draw the cross
+          shapes.push(Shape::Line {
+            color: theme.play_color,
+            radius: stroke,
+            from: [start_x, start_y],
+            to: [start_x + size, start_y + size],
+          });
+          shapes.push(Shape::Line {
+            color: theme.play_color,
+            radius: stroke,
+            from: [start_x + size, start_y],
+            to: [start_x, start_y + size],
+          });
+        }
+        Player::Naught => {
+          // A ring, so that the background shows through
+          shapes.push(Shape::Ring {
+            color: theme.play_color,
+            radius: stroke,
+            rect: [
+              start_x + stroke,
+              start_y + stroke,
+              size - (stroke * 2.0),
+              size - (stroke * 2.0),
+            ],
+          });
         }
       }
-    });
+    }
+
+    // Frame the spot chosen with the arrow keys
+    if let (Some((x, y)), None) = (self.focus, &self.pasted_area) {
+      let rect = [
+        play_size * (x - view_x) as f64 + stroke,
+        play_size * (y - view_y) as f64 + stroke,
+        play_size - stroke * 2.0,
+        play_size - stroke * 2.0,
+      ];
+      let has_highlight = theme
+        .sprites
+        .as_ref()
+        .is_some_and(|sprites| sprites.highlight.is_some());
+      shapes.push(if has_highlight {
+        Shape::Sprite {
+          sprite: Sprite::Highlight,
+          rect,
+        }
+      } else {
+        Shape::Frame {
+          color: YELLOW,
+          radius: stroke,
+          rect,
+        }
+      });
+    }
+    shapes
+  }
+
+  fn update(&mut self, args: &UpdateArgs) {
+    self.reload_changed(args.dt);
+    if let Some((_, seconds_left)) = &mut self.notice {
+      *seconds_left -= args.dt;
+      if *seconds_left <= 0.0 {
+        self.notice = None;
+      }
+    }
+    if self.game_area.winner().is_some() || self.out_of_time.is_some() {
+      return;
+    }
+    // The clock of the player in turn runs until they play
+    if let Some(clocks) = &self.clocks {
+      self.thinking_seconds += args.dt;
+      let player = self.player_in_turn();
+      if clocks.is_out_of_time(player, self.thinking_ms()) {
+        self.out_of_time = Some(player);
+        self.tell(&tr!(
+          "player-out-of-time",
+          player = self.player_name(player)
+        ));
+      }
+    }
   }
+}
+
+/// Why a play made in the window wasn't made
+#[derive(Debug)]
+enum PlayError {
+  Illegal(IllegalPlayError),
+  /// The player's time ran out before they played
+  OutOfTime,
+  /// The play couldn't be written into the journal
+  Journal(io::Error),
+}
+
+/// Makes a play after checking it against the rules and the player's clock.
+/// A play made after the player's time ran out is neither written into the
+/// journal nor marked, even if it would have won.
+fn make_play(
+  game_area: &mut GameArea,
+  clocks: Option<&mut Clocks>,
+  journal: Option<&mut Journal>,
+  (player, x, y): (Player, i128, i128),
+  thinking_ms: u64,
+) -> Result<(), PlayError> {
+  game_area
+    .validate_play(player, x, y)
+    .map_err(PlayError::Illegal)?;
+  if let Some(clocks) = &clocks {
+    if clocks.is_out_of_time(player, thinking_ms) {
+      return Err(PlayError::OutOfTime);
+    }
+  }
+  if let Some(journal) = journal {
+    // A play which couldn't be written down would be lost in a crash
+    journal.append(player, x, y).map_err(PlayError::Journal)?;
+  }
+  game_area.play(player, x, y).map_err(PlayError::Illegal)?;
+  if let Some(clocks) = clocks {
+    // The time was checked before the play, so this can't run out
+    let _ = clocks.record_play(player, thinking_ms);
+  }
+  Ok(())
+}
+
+fn symbol(player: Player) -> char {
+  match player {
+    Player::Naught => 'o',
+    Player::Cross => 'x',
+  }
+}
+
+fn is_modifier(key: Key) -> bool {
+  matches!(key, Key::LCtrl | Key::RCtrl | Key::LGui | Key::RGui)
+}
+
+fn is_shift(key: Key) -> bool {
+  matches!(key, Key::LShift | Key::RShift)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use first_to_five::rules::TimeControl;
+
+  #[test]
+  fn test_flag_falls_before_winning_play() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, 1)?;
+    }
+    let mut clocks = Clocks::new(TimeControl::Clock {
+      initial_ms: 1000,
+      increment_ms: 0,
+    });
+
+    let result = make_play(
+      &mut area,
+      Some(&mut clocks),
+      None,
+      (Player::Cross, 4, 0),
+      1001,
+    );
+    assert!(matches!(result, Err(PlayError::OutOfTime)));
+    assert_eq!(area.winner(), None);
+    assert_eq!(area.moves().count(), 8);
+    assert_eq!(clocks.remaining_ms(Player::Cross), Some(1000));
+
+    // The same play in time wins
+    make_play(
+      &mut area,
+      Some(&mut clocks),
+      None,
+      (Player::Cross, 4, 0),
+      1000,
+    )
+    .unwrap();
+    assert_eq!(area.winner(), Some(Player::Cross));
+    assert_eq!(clocks.remaining_ms(Player::Cross), Some(0));
 
-  fn update(&mut self, _args: &UpdateArgs) {
-    // TODO: Do something
+    Ok(())
   }
 }
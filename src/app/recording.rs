@@ -0,0 +1,183 @@
+//! Recording everything that happens in the game window into a file, and
+//! playing it back, so that a problem seen once can be seen again.
+//!
+//! A recording is a JSON object per line. The first one tells the
+//! [`RECORDING_FORMAT_VERSION`] it was written in, the second what the game
+//! started from, and the rest are the window events in the order they came,
+//! including the render and update events with the time that passed between
+//! them. Feeding the same events to an [`App`](super::App) made from the same
+//! start leads to the same game. Recordings of version 1 have no version
+//! line.
+use super::PlayerNames;
+use first_to_five::rules::{GameArea, Play, TimeControl, UnsupportedVersionError};
+use piston::input::{Event, Input, Loop};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The version of the format games are recorded in
+pub const RECORDING_FORMAT_VERSION: u32 = 2;
+
+/// The first line of a recording
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+  version: u32,
+}
+
+/// What a recorded game started from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Start {
+  /// The plays made before the window was opened
+  pub moves: Vec<Play>,
+  pub names: Option<PlayerNames>,
+  pub time_control: Option<TimeControl>,
+  /// The settings which decide where a click lands on the board
+  pub play_size: f64,
+  pub scale_factor: f64,
+}
+
+impl Start {
+  /// The position the game started from
+  pub fn area(&self) -> GameArea {
+    let mut area = GameArea::default();
+    for play in &self.moves {
+      // The plays were made on a game area, so they can be made again
+      let _ = area.mark(play.player(), play.x(), play.y());
+    }
+    area
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Entry {
+  Start(Start),
+  Input(Input),
+  Loop(Loop),
+}
+
+/// A recording read back from a file
+pub struct Recording {
+  pub start: Start,
+  pub events: Vec<Event>,
+}
+
+fn invalid_data(error: impl ToString) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+impl Recording {
+  pub fn load(path: &Path) -> io::Result<Recording> {
+    let mut lines = BufReader::new(File::open(path)?).lines().peekable();
+    if let Some(Ok(Ok(header))) = lines.peek().map(|line| {
+      line
+        .as_ref()
+        .map(|line| serde_json::from_str::<Header>(line))
+    }) {
+      UnsupportedVersionError::check(header.version, RECORDING_FORMAT_VERSION)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+      lines.next();
+    }
+    // The events have kept their shape since version 1, so there is nothing
+    // to migrate yet
+    let start = match lines.next() {
+      Some(line) => match serde_json::from_str(&line?).map_err(invalid_data)? {
+        Entry::Start(start) => start,
+        _ => return Err(invalid_data("The recording doesn't start with a start")),
+      },
+      None => return Err(invalid_data("The recording is empty")),
+    };
+    let mut events = vec![];
+    for line in lines {
+      match serde_json::from_str(&line?).map_err(invalid_data)? {
+        Entry::Start(_) => return Err(invalid_data("The recording starts twice")),
+        Entry::Input(input) => events.push(Event::Input(input, None)),
+        Entry::Loop(event) => events.push(Event::Loop(event)),
+      }
+    }
+    Ok(Recording { start, events })
+  }
+}
+
+/// Writes the events of a game window into a file as they come
+pub struct Recorder {
+  file: BufWriter<File>,
+}
+
+impl Recorder {
+  pub fn create(path: &Path, start: Start) -> io::Result<Recorder> {
+    let mut recorder = Recorder {
+      file: BufWriter::new(File::create(path)?),
+    };
+    recorder.write(&Header {
+      version: RECORDING_FORMAT_VERSION,
+    })?;
+    recorder.write(&Entry::Start(start))?;
+    Ok(recorder)
+  }
+
+  /// Appends the event to the file right away, so that it is there even if
+  /// the game crashes on it
+  pub fn record(&mut self, event: &Event) -> io::Result<()> {
+    match event {
+      Event::Input(input, _) => self.write(&Entry::Input(input.clone())),
+      Event::Loop(event) => self.write(&Entry::Loop(*event)),
+      // Nothing in the game sends custom events
+      Event::Custom(..) => Ok(()),
+    }
+  }
+
+  fn write(&mut self, entry: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut self.file, entry).map_err(invalid_data)?;
+    writeln!(self.file)?;
+    self.file.flush()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use piston::input::{Button, ButtonArgs, ButtonState, Key, UpdateArgs};
+  use std::env;
+
+  #[test]
+  fn test_record_and_load() {
+    let path = env::temp_dir().join("first-to-five-test-recording.jsonl");
+    let area = GameArea::from_move_list("x0,0 o1,1").unwrap();
+    let start = Start {
+      moves: area.moves().copied().collect(),
+      names: None,
+      time_control: Some(TimeControl::minutes(3, 2)),
+      play_size: 80.0,
+      scale_factor: 2.0,
+    };
+    let press = Input::Button(ButtonArgs {
+      state: ButtonState::Press,
+      button: Button::Keyboard(Key::Space),
+      scancode: None,
+    });
+    let mut recorder = Recorder::create(&path, start).unwrap();
+    recorder.record(&Event::Input(press.clone(), None)).unwrap();
+    let update = Loop::Update(UpdateArgs { dt: 0.25 });
+    recorder.record(&Event::Loop(update)).unwrap();
+
+    let recording = Recording::load(&path).unwrap();
+    assert_eq!(recording.start.area().to_string(), area.to_string());
+    assert_eq!(
+      recording.start.time_control,
+      Some(TimeControl::minutes(3, 2))
+    );
+    match recording.events.as_slice() {
+      [Event::Input(input, None), Event::Loop(event)] => {
+        assert_eq!(*input, press);
+        assert_eq!(*event, update);
+      }
+      events => panic!("Unexpected events: {:?}", events),
+    }
+
+    std::fs::write(&path, "{\"version\":99}\n").unwrap();
+    assert!(Recording::load(&path).is_err());
+    let _ = std::fs::remove_file(path);
+  }
+}
@@ -0,0 +1,71 @@
+//! Reading texts aloud with the speech synthesizer of the platform.
+//!
+//! The texts are spoken one at a time by a thread of their own, so that
+//! announcements don't talk over each other. Only the newest of the texts
+//! given while another one is spoken waits for its turn, so that quick plays
+//! don't build up a backlog of announcements which are out of date by the
+//! time they are spoken.
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+thread_local! {
+  /// The texts waiting to be spoken
+  static QUEUE: Sender<String> = start_speaking();
+}
+
+#[cfg(target_os = "macos")]
+fn command(text: &str) -> Command {
+  let mut command = Command::new("say");
+  // A text starting with a dash isn't an option
+  command.arg("--").arg(text);
+  command
+}
+
+#[cfg(target_os = "windows")]
+fn command(text: &str) -> Command {
+  let mut command = Command::new("powershell");
+  command.args(["-NoProfile", "-Command"]).arg(format!(
+    "Add-Type -AssemblyName System.Speech; \
+     (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+    text.replace('\'', "''")
+  ));
+  command
+}
+
+/// Speech Dispatcher, which also speaks through the screen reader Orca
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn command(text: &str) -> Command {
+  let mut command = Command::new("spd-say");
+  // Without waiting, the next text would start while this one is spoken. A
+  // text starting with a dash isn't an option.
+  command.args(["--wait", "--"]).arg(text);
+  command
+}
+
+/// Starts the thread speaking the texts sent to it, one after another,
+/// skipping those replaced by newer ones while it was speaking
+fn start_speaking() -> Sender<String> {
+  let (sender, receiver) = mpsc::channel::<String>();
+  thread::spawn(move || {
+    while let Ok(text) = receiver.recv() {
+      let text = receiver.try_iter().last().unwrap_or(text);
+      let _ = command(&text)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    }
+  });
+  sender
+}
+
+/// Speaks the text once the text being spoken has finished, without waiting
+/// for it. The text is skipped if another one is given before its turn.
+/// Nothing is spoken if the platform has no speech synthesizer installed.
+pub fn speak(text: &str) {
+  QUEUE.with(|queue| {
+    // The thread only stops when the queue is dropped
+    let _ = queue.send(text.to_owned());
+  });
+}
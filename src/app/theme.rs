@@ -0,0 +1,184 @@
+//! How the board looks: the background under the grid, the colors of the
+//! lines and images drawn in place of the plays.
+//!
+//! A theme is a directory, `assets/theme` unless `FIRST_TO_FIVE_THEME` points
+//! elsewhere. It may have a `theme.toml` picking the background:
+//!
+//! ```toml
+//! # One of "plain", "paper" or "texture", which tiles background.png
+//! background = "paper"
+//! ```
+//!
+//! and `cross.png`, `naught.png` and `highlight.png` images for the plays.
+use opengl_graphics::{Texture, TextureSettings};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the theme is looked for unless `FIRST_TO_FIVE_THEME` points
+/// elsewhere
+const DEFAULT_THEME: &str = "assets/theme";
+
+/// The files a theme is made of
+const FILES: &[&str] = &[
+  "theme.toml",
+  "background.png",
+  "cross.png",
+  "naught.png",
+  "highlight.png",
+];
+
+/// How many plays wide and high a single tile of a background texture is
+pub const TILE_PLAYS: i128 = 4;
+
+/// The images of a theme. They are stretched to the size of a play.
+pub struct Sprites {
+  pub cross: Texture,
+  pub naught: Texture,
+  /// Drawn under the spot chosen with the arrow keys
+  pub highlight: Option<Texture>,
+}
+
+/// What is drawn under the grid
+pub enum Background {
+  Color([f32; 4]),
+  /// An image repeated over the board, moving along with it
+  Texture(Texture),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BackgroundKind {
+  /// Black, like the game has always been
+  Plain,
+  /// Squared paper
+  Paper,
+  /// `background.png` repeated, like the grain of a wooden board
+  Texture,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThemeFile {
+  background: Option<BackgroundKind>,
+}
+
+pub struct Theme {
+  pub background: Background,
+  pub grid_color: [f32; 4],
+  /// The color of the plays drawn with lines
+  pub play_color: [f32; 4],
+  pub sprites: Option<Sprites>,
+}
+
+fn load_texture(path: &Path) -> Option<Texture> {
+  if !path.exists() {
+    return None;
+  }
+  match Texture::from_path(path, &TextureSettings::new()) {
+    Ok(texture) => Some(texture),
+    Err(error) => {
+      log!(
+        "{}",
+        tr!(
+          "theme-load-failed",
+          path = path.display().to_string(),
+          error = error
+        )
+      );
+      None
+    }
+  }
+}
+
+/// Reads `theme.toml`, which is fine to leave out. The error is translated
+/// for the window title and the console.
+fn read_theme_file(path: &Path) -> Result<Option<ThemeFile>, String> {
+  let text = match fs::read_to_string(path) {
+    Ok(text) => text,
+    Err(_) => return Ok(None),
+  };
+  toml::from_str(&text).map(Some).map_err(|error| {
+    tr!(
+      "theme-read-failed",
+      path = path.display().to_string(),
+      error = error.to_string()
+    )
+  })
+}
+
+impl Sprites {
+  /// Loads `cross.png`, `naught.png` and, if there is one, `highlight.png`
+  /// from the directory. Without both plays there are no sprites, and the
+  /// plays are drawn with lines instead.
+  pub fn load(directory: &Path) -> Option<Sprites> {
+    Some(Sprites {
+      cross: load_texture(&directory.join("cross.png"))?,
+      naught: load_texture(&directory.join("naught.png"))?,
+      highlight: load_texture(&directory.join("highlight.png")),
+    })
+  }
+}
+
+impl Theme {
+  /// Loads the theme in the directory. The background is a texture if the
+  /// directory has `background.png` and `theme.toml` doesn't pick another
+  /// one, and plain otherwise. An invalid `theme.toml` is an error, told in
+  /// the language of the game like the images which couldn't be loaded.
+  pub fn load(directory: &Path) -> Result<Theme, String> {
+    let chosen = read_theme_file(&directory.join("theme.toml"))?.and_then(|file| file.background);
+    Ok(Theme::with_background(directory, chosen))
+  }
+
+  fn with_background(directory: &Path, chosen: Option<BackgroundKind>) -> Theme {
+    let texture = match chosen {
+      None | Some(BackgroundKind::Texture) => load_texture(&directory.join("background.png")),
+      Some(_) => None,
+    };
+    let (background, grid_color, play_color) = match (chosen, texture) {
+      (_, Some(texture)) => (
+        Background::Texture(texture),
+        [0.2, 0.1, 0.0, 0.6],
+        [0.0, 0.0, 0.0, 1.0],
+      ),
+      (Some(BackgroundKind::Paper), None) => (
+        Background::Color([0.98, 0.97, 0.92, 1.0]),
+        [0.6, 0.75, 0.9, 1.0],
+        [0.1, 0.15, 0.4, 1.0],
+      ),
+      _ => (
+        Background::Color([0.0, 0.0, 0.0, 1.0]),
+        [0.5, 0.5, 0.5, 1.0],
+        [1.0, 1.0, 1.0, 1.0],
+      ),
+    };
+    Theme {
+      background,
+      grid_color,
+      play_color,
+      sprites: Sprites::load(directory),
+    }
+  }
+
+  /// The directory `FIRST_TO_FIVE_THEME` points to, or `assets/theme`
+  pub fn directory() -> PathBuf {
+    env::var_os("FIRST_TO_FIVE_THEME")
+      .map(PathBuf::from)
+      .unwrap_or_else(|| PathBuf::from(DEFAULT_THEME))
+  }
+
+  /// Every file of the theme in the directory, whether it exists or not
+  pub fn files(directory: &Path) -> Vec<PathBuf> {
+    FILES.iter().map(|file| directory.join(file)).collect()
+  }
+
+  /// Loads the theme of the [`Theme::directory`], ignoring an invalid
+  /// `theme.toml`
+  pub fn from_environment() -> Theme {
+    let directory = Theme::directory();
+    Theme::load(&directory).unwrap_or_else(|error| {
+      log!("{}", error);
+      Theme::with_background(&directory, None)
+    })
+  }
+}
@@ -0,0 +1,56 @@
+//! Noticing when files the game has read are changed, so that the changes
+//! can be applied without restarting the game.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// How often the files are checked, in seconds
+const CHECK_INTERVAL: f64 = 1.0;
+
+/// Watches files by checking when they were last modified every now and
+/// then. Files which don't exist are watched for being created.
+pub struct Watcher {
+  files: Vec<(PathBuf, Option<SystemTime>)>,
+  since_check: f64,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+}
+
+impl Watcher {
+  pub fn new(paths: Vec<PathBuf>) -> Watcher {
+    let files = paths
+      .into_iter()
+      .map(|path| {
+        let modified = modified(&path);
+        (path, modified)
+      })
+      .collect();
+    Watcher {
+      files,
+      since_check: 0.0,
+    }
+  }
+
+  /// Whether any of the files has changed since the last check, given the
+  /// seconds passed since the last call
+  pub fn changed(&mut self, seconds: f64) -> bool {
+    self.since_check += seconds;
+    if self.since_check < CHECK_INTERVAL {
+      return false;
+    }
+    self.since_check = 0.0;
+    let mut changed = false;
+    for (path, last_modified) in &mut self.files {
+      let modified = modified(path);
+      if modified != *last_modified {
+        *last_modified = modified;
+        changed = true;
+      }
+    }
+    changed
+  }
+}
@@ -0,0 +1,17 @@
+//! Runs the HTTP API of [`first_to_five::server`].
+//!
+//! The address to listen on can be given as the first argument, and defaults
+//! to `127.0.0.1:3000`.
+use first_to_five::server;
+use std::env;
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+  let address = env::args()
+    .nth(1)
+    .unwrap_or_else(|| String::from("127.0.0.1:3000"));
+  let listener = TcpListener::bind(&address).await?;
+  println!("Listening on http://{}", listener.local_addr()?);
+  server::serve(listener).await
+}
@@ -0,0 +1,214 @@
+//! Export of many games as one table for data analysis.
+//!
+//! Every move of every game becomes one row, so the result can be loaded
+//! into pandas or Polars as is. The columns are:
+//!
+//! | Column          | Type      | Description                              |
+//! |-----------------|-----------|------------------------------------------|
+//! | `game_id`       | `UInt64`  | Id of the game given by the caller       |
+//! | `move_index`    | `UInt32`  | Index of the move within the game        |
+//! | `player`        | `Utf8`    | `"Naught"` or `"Cross"`                  |
+//! | `x`, `y`        | `Int64`   | Coordinates of the play                  |
+//! | `think_time_ms` | `UInt64`  | How long the player thought, if known    |
+//! | `eval`          | `Float64` | Evaluation of the position, if known     |
+//! | `winner`        | `Utf8`    | Winner of the whole game, null if none   |
+use crate::rules::{coordinate_to_i64, CoordinateOutOfRangeError, GameArea, Player};
+use arrow::array::{
+  ArrayRef, Float64Builder, Int64Builder, StringBuilder, UInt32Builder, UInt64Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+
+/// A game to export, with the details of its moves which the rules don't
+/// keep track of
+#[derive(Debug, Clone)]
+pub struct ExportedGame<'a> {
+  pub id: u64,
+  pub area: &'a GameArea,
+  /// Thinking time of each move in milliseconds, indexed like the moves.
+  /// Missing entries are exported as nulls.
+  pub think_times_ms: Vec<Option<u64>>,
+  /// Evaluation of the position after each move, indexed like the moves.
+  /// Missing entries are exported as nulls.
+  pub evals: Vec<Option<f64>>,
+}
+
+impl<'a> ExportedGame<'a> {
+  /// A game without thinking times or evaluations
+  pub fn new(id: u64, area: &'a GameArea) -> Self {
+    ExportedGame {
+      id,
+      area,
+      think_times_ms: vec![],
+      evals: vec![],
+    }
+  }
+}
+
+/// Error caused by trying to export games which don't fit the table
+#[derive(Debug)]
+pub enum ExportError {
+  /// The coordinates are stored as 64-bit integers, and a play was further
+  /// away from the origin than that
+  CoordinateOutOfRange(CoordinateOutOfRangeError),
+  Arrow(ArrowError),
+  Parquet(ParquetError),
+}
+impl std::error::Error for ExportError {}
+impl fmt::Display for ExportError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ExportError::CoordinateOutOfRange(error) => write!(f, "{}", error),
+      ExportError::Arrow(error) => write!(f, "Couldn't build the table: {}", error),
+      ExportError::Parquet(error) => write!(f, "Couldn't write Parquet: {}", error),
+    }
+  }
+}
+impl From<CoordinateOutOfRangeError> for ExportError {
+  fn from(error: CoordinateOutOfRangeError) -> Self {
+    ExportError::CoordinateOutOfRange(error)
+  }
+}
+impl From<ArrowError> for ExportError {
+  fn from(error: ArrowError) -> Self {
+    ExportError::Arrow(error)
+  }
+}
+impl From<ParquetError> for ExportError {
+  fn from(error: ParquetError) -> Self {
+    ExportError::Parquet(error)
+  }
+}
+
+/// The schema of the table, see the module documentation
+pub fn schema() -> Schema {
+  Schema::new(vec![
+    Field::new("game_id", DataType::UInt64, false),
+    Field::new("move_index", DataType::UInt32, false),
+    Field::new("player", DataType::Utf8, false),
+    Field::new("x", DataType::Int64, false),
+    Field::new("y", DataType::Int64, false),
+    Field::new("think_time_ms", DataType::UInt64, true),
+    Field::new("eval", DataType::Float64, true),
+    Field::new("winner", DataType::Utf8, true),
+  ])
+}
+
+/// Builds the table of the moves of every given game
+pub fn to_record_batch(games: &[ExportedGame]) -> Result<RecordBatch, ExportError> {
+  let rows = games.iter().map(|game| game.area.moves().count()).sum();
+  let mut game_ids = UInt64Builder::with_capacity(rows);
+  let mut move_indices = UInt32Builder::with_capacity(rows);
+  let mut players = StringBuilder::new();
+  let mut xs = Int64Builder::with_capacity(rows);
+  let mut ys = Int64Builder::with_capacity(rows);
+  let mut think_times = UInt64Builder::with_capacity(rows);
+  let mut evals = Float64Builder::with_capacity(rows);
+  let mut winners = StringBuilder::new();
+
+  for game in games {
+    let winner = game.area.winner().map(Player::name);
+    for (index, play) in game.area.moves().enumerate() {
+      game_ids.append_value(game.id);
+      move_indices.append_value(index as u32);
+      players.append_value(play.player().name());
+      xs.append_value(coordinate_to_i64(play.x())?);
+      ys.append_value(coordinate_to_i64(play.y())?);
+      think_times.append_option(game.think_times_ms.get(index).copied().flatten());
+      evals.append_option(game.evals.get(index).copied().flatten());
+      winners.append_option(winner);
+    }
+  }
+
+  let columns: Vec<ArrayRef> = vec![
+    Arc::new(game_ids.finish()),
+    Arc::new(move_indices.finish()),
+    Arc::new(players.finish()),
+    Arc::new(xs.finish()),
+    Arc::new(ys.finish()),
+    Arc::new(think_times.finish()),
+    Arc::new(evals.finish()),
+    Arc::new(winners.finish()),
+  ];
+  Ok(RecordBatch::try_new(Arc::new(schema()), columns)?)
+}
+
+/// Writes the table of the moves of every given game as a Parquet file
+pub fn write_parquet<W: Write + Send>(
+  games: &[ExportedGame],
+  writer: W,
+) -> Result<(), ExportError> {
+  let batch = to_record_batch(games)?;
+  let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+  writer.write(&batch)?;
+  writer.close()?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::IllegalPlayError;
+  use arrow::array::{Array, Int64Array, StringArray, UInt64Array};
+
+  #[test]
+  fn test_one_row_per_move() -> Result<(), IllegalPlayError> {
+    let mut first = GameArea::default();
+    first.play(Player::Cross, 0, 0)?;
+    first.play(Player::Naught, -3, 2)?;
+    let mut second = GameArea::default();
+    second.play(Player::Naught, 1, 1)?;
+
+    let mut exported = ExportedGame::new(7, &first);
+    exported.think_times_ms = vec![Some(1200)];
+    let batch = to_record_batch(&[exported, ExportedGame::new(8, &second)]).unwrap();
+    assert_eq!(batch.num_rows(), 3);
+
+    let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+    let game_ids = column("game_id");
+    let game_ids = game_ids.as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(game_ids.values().to_vec(), vec![7, 7, 8]);
+    let xs = column("x");
+    let xs = xs.as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(xs.values().to_vec(), vec![0, -3, 1]);
+    let players = column("player");
+    let players = players.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(players.value(1), "Naught");
+    let think_times = column("think_time_ms");
+    assert_eq!(think_times.null_count(), 2);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_parquet() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    area.play(Player::Cross, 0, 0)?;
+
+    let mut bytes = vec![];
+    write_parquet(&[ExportedGame::new(1, &area)], &mut bytes).unwrap();
+    assert_eq!(&bytes[..4], b"PAR1");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_refuses_huge_coordinates() -> Result<(), IllegalPlayError> {
+    let far_away = i64::MAX as i128 + 1;
+    let mut area = GameArea::default();
+    area.play(Player::Cross, far_away, 0)?;
+
+    assert!(matches!(
+      to_record_batch(&[ExportedGame::new(1, &area)]),
+      Err(ExportError::CoordinateOutOfRange(CoordinateOutOfRangeError(coordinate))) if coordinate == far_away
+    ));
+
+    Ok(())
+  }
+}
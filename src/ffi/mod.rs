@@ -0,0 +1,294 @@
+//! C bindings for embedding the rules in other applications.
+//!
+//! Build them with `cargo build --release --features ffi`, and use the
+//! header at `include/first_to_five.h`. After changing anything here,
+//! regenerate the header with:
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/first_to_five.h
+//! ```
+//!
+//! A panic unwinding out of an `extern "C"` function would abort the
+//! application, so every function catches them and reports a failure
+//! instead.
+use crate::rules::{GameArea, IllegalPlayError, Player};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Runs the body of a function, returning `on_panic` if it panics
+fn catch_panic<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+  panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(on_panic)
+}
+
+/// A player, or the lack of one
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FtfPlayer {
+  None = 0,
+  Naught = 1,
+  Cross = 2,
+}
+impl From<Option<Player>> for FtfPlayer {
+  fn from(player: Option<Player>) -> Self {
+    match player {
+      None => FtfPlayer::None,
+      Some(Player::Naught) => FtfPlayer::Naught,
+      Some(Player::Cross) => FtfPlayer::Cross,
+    }
+  }
+}
+
+/// The outcome of trying to mark a play
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FtfMarkResult {
+  Ok = 0,
+  WinnerAlreadySelected = 1,
+  NotPlayersTurn = 2,
+  AlreadyMarked = 3,
+  /// The area pointer was null or the player wasn't `FtfPlayer::Naught` or
+  /// `FtfPlayer::Cross`
+  InvalidArgument = 4,
+  /// The library ran into a bug and the play may have been marked only
+  /// partly, so the area shouldn't be played on anymore
+  InternalError = 5,
+}
+impl From<Result<(), IllegalPlayError>> for FtfMarkResult {
+  fn from(result: Result<(), IllegalPlayError>) -> Self {
+    match result {
+      Ok(()) => FtfMarkResult::Ok,
+      Err(IllegalPlayError::WinnerAlreadySelected) => FtfMarkResult::WinnerAlreadySelected,
+      Err(IllegalPlayError::NotPlayersTurn(_)) => FtfMarkResult::NotPlayersTurn,
+      Err(IllegalPlayError::AlreadyMarked(_)) => FtfMarkResult::AlreadyMarked,
+      // 64-bit coordinates are always within bounds
+      Err(IllegalPlayError::OutOfBounds) => FtfMarkResult::InvalidArgument,
+    }
+  }
+}
+
+/// Creates a new empty game area, or returns null if it couldn't be created.
+/// Free it with `ftf_game_area_free`.
+#[no_mangle]
+pub extern "C" fn ftf_game_area_new() -> *mut GameArea {
+  catch_panic(ptr::null_mut(), || {
+    Box::into_raw(Box::new(GameArea::default()))
+  })
+}
+
+/// Frees a game area created with `ftf_game_area_new`. Passing null is a
+/// no-op.
+///
+/// # Safety
+///
+/// `area` must be null or a pointer from `ftf_game_area_new` which hasn't
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_game_area_free(area: *mut GameArea) {
+  catch_panic((), || {
+    if !area.is_null() {
+      drop(Box::from_raw(area));
+    }
+  })
+}
+
+/// Marks a new play, checking first that the rules allow it. The player is
+/// one of the `FtfPlayer` values, taken as an integer as C allows passing
+/// any.
+///
+/// # Safety
+///
+/// `area` must be null or a valid pointer from `ftf_game_area_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_game_area_mark(
+  area: *mut GameArea,
+  player: c_int,
+  x: i64,
+  y: i64,
+) -> FtfMarkResult {
+  catch_panic(FtfMarkResult::InternalError, || {
+    let area = match area.as_mut() {
+      Some(area) => area,
+      None => return FtfMarkResult::InvalidArgument,
+    };
+    let player = match player {
+      player if player == FtfPlayer::Naught as c_int => Player::Naught,
+      player if player == FtfPlayer::Cross as c_int => Player::Cross,
+      _ => return FtfMarkResult::InvalidArgument,
+    };
+    area.play(player, x as i128, y as i128).into()
+  })
+}
+
+/// The winner of the game, or `FTF_PLAYER_NONE` if nobody has won yet
+///
+/// # Safety
+///
+/// `area` must be null or a valid pointer from `ftf_game_area_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_game_area_winner(area: *const GameArea) -> FtfPlayer {
+  catch_panic(FtfPlayer::None, || match area.as_ref() {
+    Some(area) => area.winner().into(),
+    None => FtfPlayer::None,
+  })
+}
+
+/// The player whose turn it is, or `FTF_PLAYER_NONE` before the first play
+///
+/// # Safety
+///
+/// `area` must be null or a valid pointer from `ftf_game_area_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_game_area_next_player(area: *const GameArea) -> FtfPlayer {
+  catch_panic(FtfPlayer::None, || match area.as_ref() {
+    Some(area) => area.next_player().into(),
+    None => FtfPlayer::None,
+  })
+}
+
+/// The whole game in the JSON interchange format, as a NUL-terminated UTF-8
+/// string. Free it with `ftf_string_free`. Returns null if `area` is null or
+/// the game couldn't be serialized.
+///
+/// # Safety
+///
+/// `area` must be null or a valid pointer from `ftf_game_area_new`.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_game_area_serialize(area: *const GameArea) -> *mut c_char {
+  catch_panic(ptr::null_mut(), || match area.as_ref() {
+    // JSON escapes control characters, so there can't be NUL bytes in it
+    Some(area) => CString::new(area.to_json()).unwrap().into_raw(),
+    None => ptr::null_mut(),
+  })
+}
+
+/// Creates a game area from JSON produced by `ftf_game_area_serialize`.
+/// Returns null if the JSON isn't a valid game. Free the area with
+/// `ftf_game_area_free`.
+///
+/// # Safety
+///
+/// `json` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_game_area_deserialize(json: *const c_char) -> *mut GameArea {
+  catch_panic(ptr::null_mut(), || {
+    if json.is_null() {
+      return ptr::null_mut();
+    }
+    let area = CStr::from_ptr(json)
+      .to_str()
+      .ok()
+      .and_then(|json| GameArea::from_json(json).ok());
+    match area {
+      Some(area) => Box::into_raw(Box::new(area)),
+      None => ptr::null_mut(),
+    }
+  })
+}
+
+/// Frees a string returned by this library. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this library which hasn't
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn ftf_string_free(string: *mut c_char) {
+  catch_panic((), || {
+    if !string.is_null() {
+      drop(CString::from_raw(string));
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_game_through_ffi() {
+    unsafe {
+      let area = ftf_game_area_new();
+      assert_eq!(ftf_game_area_next_player(area), FtfPlayer::None);
+      assert_eq!(
+        ftf_game_area_mark(area, FtfPlayer::Cross as c_int, 0, 0),
+        FtfMarkResult::Ok
+      );
+      assert_eq!(
+        ftf_game_area_mark(area, FtfPlayer::Cross as c_int, 1, 0),
+        FtfMarkResult::NotPlayersTurn
+      );
+      assert_eq!(
+        ftf_game_area_mark(area, FtfPlayer::Naught as c_int, 0, 0),
+        FtfMarkResult::AlreadyMarked
+      );
+      assert_eq!(
+        ftf_game_area_mark(area, FtfPlayer::Naught as c_int, 1, 0),
+        FtfMarkResult::Ok
+      );
+      assert_eq!(ftf_game_area_winner(area), FtfPlayer::None);
+
+      let serialized = ftf_game_area_serialize(area);
+      let copy = ftf_game_area_deserialize(serialized);
+      assert_eq!((*copy).to_string(), (*area).to_string());
+      assert_eq!(ftf_game_area_next_player(copy), FtfPlayer::Cross);
+      ftf_string_free(serialized);
+      ftf_game_area_free(copy);
+      ftf_game_area_free(area);
+    }
+  }
+
+  #[test]
+  fn test_null_pointers() {
+    unsafe {
+      assert_eq!(
+        ftf_game_area_mark(ptr::null_mut(), FtfPlayer::Cross as c_int, 0, 0),
+        FtfMarkResult::InvalidArgument
+      );
+      assert_eq!(ftf_game_area_winner(ptr::null()), FtfPlayer::None);
+      assert!(ftf_game_area_serialize(ptr::null()).is_null());
+      assert!(ftf_game_area_deserialize(ptr::null()).is_null());
+      assert!(ftf_game_area_deserialize(b"nope\0".as_ptr() as *const c_char).is_null());
+      ftf_game_area_free(ptr::null_mut());
+      ftf_string_free(ptr::null_mut());
+    }
+  }
+
+  #[test]
+  fn test_deserialize_refuses_coordinates_beyond_bounds() {
+    let json = format!(
+      r#"{{
+        "rules": {{ "winning_length": 5 }},
+        "moves": [{{ "x": {}, "y": 0, "player": "Cross" }}],
+        "result": {{ "status": "in_progress" }}
+      }}\0"#,
+      i128::MAX
+    );
+    unsafe {
+      assert!(ftf_game_area_deserialize(json.as_ptr() as *const c_char).is_null());
+    }
+  }
+
+  #[test]
+  fn test_panics_are_caught() {
+    assert_eq!(
+      catch_panic(FtfMarkResult::InternalError, || panic!("bug")),
+      FtfMarkResult::InternalError
+    );
+  }
+
+  #[test]
+  fn test_invalid_player() {
+    unsafe {
+      let area = ftf_game_area_new();
+      for player in &[FtfPlayer::None as c_int, 3, -1] {
+        assert_eq!(
+          ftf_game_area_mark(area, *player, 0, 0),
+          FtfMarkResult::InvalidArgument
+        );
+      }
+      ftf_game_area_free(area);
+    }
+  }
+}
@@ -0,0 +1,160 @@
+//! Translations of the texts shown to players.
+//!
+//! The texts are kept in [Fluent](https://projectfluent.org/) files under
+//! `locales/`, one directory per language, and built into the game. The
+//! language is picked from the `FIRST_TO_FIVE_LANG`, `LC_ALL`,
+//! `LC_MESSAGES` and `LANG` environment variables, and English is used for
+//! anything a translation is missing.
+//!
+//! Use the [`tr!`] macro to get a text:
+//!
+//! ```ignore
+//! println!("{}", tr!("profile-created", name = "Aino"));
+//! ```
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+use std::env;
+use unic_langid::LanguageIdentifier;
+
+/// The languages the game has been translated to, with their texts
+const LOCALES: &[(&str, &str)] = &[
+  ("en", include_str!("../../locales/en/main.ftl")),
+  ("fi", include_str!("../../locales/fi/main.ftl")),
+];
+
+/// The language texts fall back to
+const FALLBACK: &str = "en";
+
+/// The translated texts of the chosen language, followed by the fallback
+/// ones
+pub struct Localizer {
+  bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+fn bundle(code: &str, source: &str) -> FluentBundle<FluentResource> {
+  let resource = FluentResource::try_new(source.to_owned())
+    .unwrap_or_else(|(_, errors)| panic!("Invalid {} texts: {:?}", code, errors));
+  let language: LanguageIdentifier = code.parse().expect("Invalid language");
+  let mut bundle = FluentBundle::new(vec![language]);
+  // The texts go to the console and the window title, where the Unicode
+  // isolation marks around the arguments would show up
+  bundle.set_use_isolating(false);
+  bundle
+    .add_resource(resource)
+    .unwrap_or_else(|errors| panic!("Invalid {} texts: {:?}", code, errors));
+  bundle
+}
+
+/// The language asked for in the environment, like `fi` for `fi_FI.UTF-8`
+fn requested_language() -> Option<String> {
+  ["FIRST_TO_FIVE_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+    .iter()
+    .filter_map(|variable| env::var(variable).ok())
+    .find(|value| !value.is_empty())
+    .map(|value| {
+      value
+        .split(['_', '-', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+    })
+}
+
+impl Localizer {
+  /// The texts of the given language, or only the fallback ones if the
+  /// language hasn't been translated
+  pub fn new(language: Option<&str>) -> Localizer {
+    let mut bundles = vec![];
+    if let Some(&(language, source)) = LOCALES
+      .iter()
+      .find(|(code, _)| Some(*code) == language && *code != FALLBACK)
+    {
+      bundles.push(bundle(language, source));
+    }
+    let (language, source) = LOCALES
+      .iter()
+      .find(|(code, _)| *code == FALLBACK)
+      .expect("The fallback language should exist");
+    bundles.push(bundle(language, source));
+    Localizer { bundles }
+  }
+
+  /// The texts of the language asked for in the environment
+  pub fn from_environment() -> Localizer {
+    Localizer::new(requested_language().as_deref())
+  }
+
+  /// The text with the given id, or the id itself if no language has it
+  pub fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+    for bundle in &self.bundles {
+      if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+        let mut errors = vec![];
+        return bundle
+          .format_pattern(pattern, args, &mut errors)
+          .into_owned();
+      }
+    }
+    id.to_owned()
+  }
+}
+
+thread_local! {
+  static LOCALIZER: Localizer = Localizer::from_environment();
+}
+
+/// The text with the given id in the language of the player, see [`tr!`]
+pub fn translate(id: &str, args: Option<&FluentArgs>) -> String {
+  LOCALIZER.with(|localizer| localizer.format(id, args))
+}
+
+/// Translates the text with the given id, filling in the named arguments:
+/// `tr!("seating", naught = "Aino", cross = "Eero", ...)`
+macro_rules! tr {
+  ($id:expr) => {
+    $crate::i18n::translate($id, None)
+  };
+  ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+    let mut args = ::fluent_bundle::FluentArgs::new();
+    $(args.set(stringify!($name), $value);)+
+    $crate::i18n::translate($id, Some(&args))
+  }};
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The ids of the messages in the given Fluent file
+  fn message_ids(source: &str) -> Vec<&str> {
+    source
+      .lines()
+      .filter(|line| line.starts_with(|character: char| character.is_ascii_lowercase()))
+      .filter_map(|line| line.split(" =").next())
+      .collect()
+  }
+
+  #[test]
+  fn test_translations_have_every_text() {
+    let (_, fallback) = LOCALES.iter().find(|(code, _)| *code == FALLBACK).unwrap();
+    let ids = message_ids(fallback);
+    for (code, source) in LOCALES {
+      assert_eq!(message_ids(source), ids, "Texts of {} differ", code);
+    }
+  }
+
+  #[test]
+  fn test_format() {
+    let mut args = FluentArgs::new();
+    args.set("name", "Aino");
+    let english = Localizer::new(Some("sv"));
+    assert_eq!(
+      english.format("profile-created", Some(&args)),
+      "Creating a new profile for Aino"
+    );
+    let finnish = Localizer::new(Some("fi"));
+    assert_eq!(
+      finnish.format("profile-created", Some(&args)),
+      "Luodaan uusi profiili pelaajalle Aino"
+    );
+    assert_eq!(finnish.format("no-such-text", None), "no-such-text");
+  }
+}
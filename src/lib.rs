@@ -0,0 +1,40 @@
+//! The rules of First to Five, and ways to use them from other programs.
+//!
+//! Without the default `std` feature, the crate only needs `alloc`, so the
+//! rules can run on embedded devices too. JSON import and export and the
+//! foreign language bindings need `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod achievements;
+#[cfg(feature = "parquet-export")]
+pub mod dataset;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod persistence;
+#[cfg(feature = "std")]
+pub mod profiles;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+pub mod protocol;
+#[cfg(feature = "std")]
+pub mod rating;
+pub mod rules;
+#[cfg(feature = "std")]
+pub mod scenario;
+#[cfg(feature = "server")]
+pub mod server;
+// rand is only a dependency outside the browser
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod simulation;
+#[cfg(any(test, feature = "test-util"))]
+pub mod strategy;
+#[cfg(feature = "std")]
+pub mod tournament;
+#[cfg(feature = "std")]
+pub mod viewer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
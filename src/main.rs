@@ -1,52 +1,951 @@
+#[macro_use]
+mod i18n;
+#[macro_use]
 mod app;
-mod rules;
-use app::{App, AppSettings};
-use itertools::Itertools;
-use rand::random;
-use rules::{GameArea, Player};
-use winit;
+use app::recording::{Recorder, Recording, Start};
+use app::{App, AppSettings, PlayerNames};
+use first_to_five::achievements::{Achievement, FinishedGame, QUICK_WIN_PLAYS, WINNING_STREAK};
+use first_to_five::persistence::journal::{self, Journal};
+use first_to_five::profiles::{self, Profile, ProfileSettings, Profiles};
+use first_to_five::rules::{Clocks, GameArea, Player, TimeControl, PRESETS};
+use first_to_five::scenario::Scenario;
+use first_to_five::simulation::Simulation;
+use first_to_five::tournament::{Format, Tournament};
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
-use piston::window::WindowSettings;
+use piston::input::{RenderEvent, UpdateEvent};
+use piston::window::{AdvancedWindow, Window as _, WindowSettings};
 
-fn main() {
-    let mut area = GameArea::default();
-    example_play(&mut area);
-    start_gui(&mut area);
+/// Where the events of the game window come from
+enum InputMode {
+    Live,
+    /// Live, and written to a file as they come
+    Record(PathBuf),
+    /// Played back from a recording, before going live
+    Replay(Recording),
+}
+
+/// The profiles playing this session and where they are stored
+struct Session {
+    profiles: Profiles,
+    path: PathBuf,
+    names: Vec<String>,
+}
 
-    println!("\n\nGame has ended!");
+impl Session {
+    /// The settings of the first player
+    fn settings(&self) -> ProfileSettings {
+        self.profiles
+            .get(&self.names[0])
+            .map(|profile| profile.settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Who plays which symbol, if two players were given
+    fn seating(&self) -> Option<PlayerNames> {
+        match self.names.as_slice() {
+            [first, second] => {
+                let seating = self.profiles.seat(first, second).ok()?;
+                Some(PlayerNames {
+                    naught: seating.naught.name.clone(),
+                    cross: seating.cross.name.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Updates the achievements of both players after a finished game they
+    /// played from the start, and tells them about the ones they unlocked
+    fn record_game(&mut self, area: &GameArea, winner: Option<Player>) {
+        let names = match self.seating() {
+            Some(names) => names,
+            None => return,
+        };
+        for (name, player) in &[(names.naught, Player::Naught), (names.cross, Player::Cross)] {
+            let profile = self
+                .profiles
+                .get_mut(name)
+                .expect("Seated profiles should exist");
+            let game = FinishedGame {
+                area,
+                winner,
+                player: *player,
+                opponent_is_hardest_ai: false,
+            };
+            for achievement in profile.achievements.record_game(&game) {
+                log!(
+                    "{}",
+                    tr!(
+                        "achievement-unlocked",
+                        name = name.as_str(),
+                        achievement = achievement_name(achievement)
+                    )
+                );
+            }
+        }
+        if let Err(error) = self.profiles.save(&self.path) {
+            log!("{}", error);
+        }
+    }
 }
 
-fn example_play(area: &mut GameArea) {
-    let plays_one = [(0i128, 0i128), (1, 0), (4, 0), (3, 0), (2, 0)];
-    let plays_two = [(2i128, 1), (3, 2), (6, 5), (4, 3), (5, 4)];
-    let first_to_play = if random::<bool>() {
-        Player::Naught
+fn main() {
+    let seed = match select_seed() {
+        Ok(seed) => seed,
+        Err(error) => {
+            log!("{}", error);
+            return;
+        }
+    };
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("stats") => {
+            print_statistics(args.next());
+            return;
+        }
+        Some("heatmap") => {
+            print_heatmap(args.next());
+            return;
+        }
+        Some("history") => {
+            print_history(args.collect());
+            return;
+        }
+        Some("assess") => {
+            let mut position = vec![];
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    // Read by select_seed
+                    "--seed" => {
+                        args.next();
+                    }
+                    _ => position.push(arg),
+                }
+            }
+            assess(&position.join(" "), &mut seeded_rng(seed));
+            return;
+        }
+        Some("tournament") => {
+            run_tournament(args.collect());
+            return;
+        }
+        Some("replay") => {
+            replay(args.next());
+            return;
+        }
+        _ => {}
+    }
+
+    let hotseat = std::env::args().any(|arg| arg == "--hotseat");
+    let time_control = match select_time_control() {
+        Ok(time_control) => time_control,
+        Err(error) => {
+            log!("{}", error);
+            return;
+        }
+    };
+    let mut session = select_profiles();
+    let mut settings = session.as_ref().map(Session::settings).unwrap_or_default();
+    if std::env::args().any(|arg| arg == "--announce") {
+        settings.announce = true;
+    }
+    let scenario = match select_scenario() {
+        Ok(scenario) => scenario,
+        Err(error) => {
+            log!("{}", error);
+            return;
+        }
+    };
+    let journal_path = journal::default_path();
+    let recovered = journal_path.as_deref().and_then(recover_game);
+    // A scenario asked for wins over a recovered game, which was printed
+    // anyway, and a game between people starts from an empty board
+    let scenario = match scenario {
+        Some(scenario) => Some(scenario),
+        None if recovered.is_some() || hotseat => None,
+        None => Some(Scenario::default()),
+    };
+    let mut area = match scenario {
+        Some(scenario) => {
+            if !scenario.description.is_empty() {
+                log!("{}", scenario.description);
+            }
+            scenario.area
+        }
+        None => recovered.unwrap_or_default(),
+    };
+    let names = if hotseat {
+        let names = session.as_ref().and_then(Session::seating);
+        Some(names.unwrap_or_else(ask_names))
     } else {
-        Player::Cross
+        None
+    };
+    let started_at = SystemTime::now();
+    // Only whole games between the seated people count for achievements
+    let counts_for_achievements = hotseat && area.moves().next().is_none();
+    // and for ratings, when both have a profile
+    let rated = counts_for_achievements && session.as_ref().and_then(Session::seating).is_some();
+    let clocks = time_control.map(Clocks::new);
+    let input = match select_recording() {
+        Some(path) => InputMode::Record(path),
+        None => InputMode::Live,
     };
+    let mut journal = journal_path.and_then(|path| {
+        Journal::create(&path, &area)
+            .map_err(|error| log!("{}", tr!("journal-failed", error = error.to_string())))
+            .ok()
+    });
+    let settings_file = session
+        .as_ref()
+        .map(|session| (session.path.clone(), session.names[0].clone()));
+    let winner = start_gui(
+        &mut area,
+        settings,
+        settings_file,
+        names.clone(),
+        clocks,
+        input,
+        journal.as_mut(),
+    );
+    // The game ended normally, so there is nothing to recover
+    if let Some(journal) = journal {
+        if let Err(error) = journal.finish() {
+            log!("{}", tr!("journal-failed", error = error.to_string()));
+        }
+    }
 
-    let plays = plays_one.iter().interleave(&plays_two);
-    for (i, (x, y)) in plays.enumerate() {
-        let player = if i % 2 == 0 {
-            !first_to_play
+    log!("\n\n{}", tr!("game-ended"));
+    if let Some(names) = &names {
+        record_game(names, &area, winner, started_at, rated && winner.is_some());
+    }
+    if let Some(session) = &mut session {
+        if counts_for_achievements && winner.is_some() {
+            session.record_game(&area, winner);
+        }
+    }
+}
+
+/// Sets up the game left unfinished in the journal when the game last
+/// crashed, if there is one
+fn recover_game(path: &Path) -> Option<GameArea> {
+    match journal::recover(path) {
+        Ok(Some(area)) => {
+            log!("{}", tr!("journal-recovered", position = area.to_string()));
+            Some(area)
+        }
+        Ok(None) => None,
+        Err(error) => {
+            log!("{}", tr!("journal-unreadable", error = error.to_string()));
+            None
+        }
+    }
+}
+
+/// Reads the file given with `--record FILE` to record the game window into
+fn select_recording() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--record" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+/// Plays back a game recorded with `--record`, and lets the game go on from
+/// where the recording ends
+fn replay(path: Option<String>) {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            log!("{}", tr!("replay-usage"));
+            return;
+        }
+    };
+    let recording = match Recording::load(Path::new(&path)) {
+        Ok(recording) => recording,
+        Err(error) => {
+            log!("{}", tr!("replay-unreadable", error = error.to_string()));
+            return;
+        }
+    };
+    let mut area = recording.start.area();
+    let names = recording.start.names.clone();
+    let clocks = recording.start.time_control.map(Clocks::new);
+    start_gui(
+        &mut area,
+        ProfileSettings::default(),
+        None,
+        names,
+        clocks,
+        InputMode::Replay(recording),
+        None,
+    );
+}
+
+/// Reads the time control given with `--time`, like `--time 3+2`
+fn select_time_control() -> Result<Option<TimeControl>, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--time" {
+            let presets: Vec<String> = PRESETS.iter().map(ToString::to_string).collect();
+            return match args.next().map(|time| time.parse()) {
+                Some(Ok(time_control)) => Ok(Some(time_control)),
+                _ => Err(tr!("invalid-time-control", presets = presets.join(", "))),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Loads the scenario given with `--scenario NAME`, either a file or one of
+/// the scenarios in the scenarios directory
+fn select_scenario() -> Result<Option<Scenario>, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--scenario" {
+            return match args.next() {
+                Some(name) => Scenario::find(&name)
+                    .map(Some)
+                    .map_err(|error| error.to_string()),
+                None => Err(tr!("scenario-usage")),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Reads the seed given with `--seed N` for everything random, so that a
+/// game can be repeated exactly, or picks a new one
+fn select_seed() -> Result<u64, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            return args
+                .next()
+                .and_then(|seed| seed.parse().ok())
+                .ok_or_else(|| tr!("invalid-seed"));
+        }
+    }
+    Ok(random())
+}
+
+/// The random number generator everything random is drawn from, telling the
+/// seed to repeat the run with
+fn seeded_rng(seed: u64) -> StdRng {
+    log!("{}", tr!("seed", seed = seed.to_string()));
+    StdRng::seed_from_u64(seed)
+}
+
+/// Asks the names of the players on the console
+fn ask_names() -> PlayerNames {
+    let ask = |prompt: &str, default: &str| {
+        print!("{} [{}]: ", prompt, default);
+        let _ = io::stdout().flush();
+        let mut name = String::new();
+        let _ = io::stdin().read_line(&mut name);
+        match name.trim() {
+            "" => default.to_owned(),
+            name => name.to_owned(),
+        }
+    };
+    let cross = ask(&tr!("ask-cross-name"), &tr!("default-cross-name"));
+    let naught = ask(&tr!("ask-naught-name"), &tr!("default-naught-name"));
+    PlayerNames { naught, cross }
+}
+
+/// Stores a finished local game in the game database, updating the ratings
+/// of the players if the game was rated
+#[cfg(feature = "database")]
+fn record_game(
+    names: &PlayerNames,
+    area: &GameArea,
+    winner: Option<Player>,
+    started_at: SystemTime,
+    rated: bool,
+) {
+    use first_to_five::persistence::database::{self, CompletedGame, GameDatabase};
+
+    if area.moves().next().is_none() {
+        return;
+    }
+    let path = match database::default_path() {
+        Some(path) => path,
+        None => {
+            log!("{}", tr!("database-no-place"));
+            return;
+        }
+    };
+    if let Some(directory) = path.parent() {
+        let _ = std::fs::create_dir_all(directory);
+    }
+    let game = CompletedGame {
+        naught: &names.naught,
+        cross: &names.cross,
+        area,
+        winner,
+        started_at,
+        finished_at: SystemTime::now(),
+    };
+    let recorded = GameDatabase::open(&path).and_then(|mut database| {
+        if rated {
+            database.record_rated_game(&game)
         } else {
-            first_to_play
-        };
-        area.mark(player, *x, *y).expect("Nobody should've won yet");
-        if area.winner().is_some() {
+            database.record_game(&game)
+        }
+    });
+    match recorded {
+        Ok(id) => log!(
+            "{}",
+            tr!("game-saved", id = id, path = path.display().to_string())
+        ),
+        Err(error) => log!("{}", tr!("game-not-saved", error = error.to_string())),
+    }
+}
+
+#[cfg(not(feature = "database"))]
+fn record_game(
+    _names: &PlayerNames,
+    _area: &GameArea,
+    _winner: Option<Player>,
+    _started_at: SystemTime,
+    _rated: bool,
+) {
+}
+
+/// The rating of the player in the game database
+#[cfg(feature = "database")]
+fn rating(name: &str) -> Option<f64> {
+    use first_to_five::persistence::database::{self, GameDatabase};
+
+    let path = database::default_path()?;
+    GameDatabase::open(&path)
+        .and_then(|database| database.rating(name))
+        .ok()
+}
+
+#[cfg(not(feature = "database"))]
+fn rating(_name: &str) -> Option<f64> {
+    None
+}
+
+/// Sets up a tournament with `tournament new round-robin|knockout NAME...`,
+/// plays its next game with `tournament` or shows how it is going with
+/// `tournament standings`. The tournament is kept in `tournament.toml` in the
+/// current directory between the games.
+fn run_tournament(args: Vec<String>) {
+    let path = Path::new("tournament.toml");
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["new", format, names @ ..] => {
+            let format = match *format {
+                "round-robin" => Format::RoundRobin,
+                "knockout" => Format::Knockout,
+                _ => {
+                    println!("{}", tr!("tournament-invalid-format"));
+                    return;
+                }
+            };
+            if path.exists() {
+                println!(
+                    "{}",
+                    tr!("tournament-exists", path = path.display().to_string())
+                );
+                return;
+            }
+            let names = names.iter().map(|name| name.to_string()).collect();
+            Tournament::new(format, names).and_then(|tournament| {
+                print_pairings(&tournament);
+                tournament.save(path)
+            })
+        }
+        ["standings"] => Tournament::load(path).map(|tournament| print_standings(&tournament)),
+        [] => Tournament::load(path).and_then(|mut tournament| {
+            let names = match tournament.next_game() {
+                Some(pairing) => PlayerNames {
+                    naught: pairing.naught.clone(),
+                    cross: pairing.cross.clone(),
+                },
+                None => {
+                    print_standings(&tournament);
+                    return Ok(());
+                }
+            };
             println!(
-                "Longest line: {:?}",
-                area.longest_consecutive_line(*x, *y).unwrap()
+                "{}",
+                tr!(
+                    "tournament-next-game",
+                    cross = names.cross.as_str(),
+                    naught = names.naught.as_str()
+                )
+            );
+            let mut area = GameArea::default();
+            let started_at = SystemTime::now();
+            let winner = start_gui(
+                &mut area,
+                ProfileSettings::default(),
+                None,
+                Some(names.clone()),
+                None,
+                InputMode::Live,
+                None,
             );
-            break;
+            record_game(&names, &area, winner, started_at, false);
+            tournament.record_result(winner)?;
+            tournament.save(path)?;
+            print_standings(&tournament);
+            Ok(())
+        }),
+        _ => {
+            println!("{}", tr!("tournament-usage"));
+            return;
+        }
+    };
+    if let Err(error) = result {
+        println!("{}", error);
+    }
+}
+
+fn print_pairings(tournament: &Tournament) {
+    for pairing in tournament.pairings() {
+        println!(
+            "{}",
+            tr!(
+                "tournament-round",
+                round = pairing.round,
+                cross = pairing.cross.as_str(),
+                naught = pairing.naught.as_str()
+            )
+        );
+    }
+}
+
+fn print_standings(tournament: &Tournament) {
+    println!("{}", tr!("tournament-standings"));
+    for (place, standing) in tournament.standings().iter().enumerate() {
+        println!(
+            "{}",
+            tr!(
+                "tournament-standing",
+                place = place + 1,
+                name = standing.name.as_str(),
+                points = standing.points,
+                wins = standing.wins,
+                losses = standing.losses,
+                unfinished = standing.unfinished
+            )
+        );
+    }
+    match (tournament.winner(), tournament.next_game()) {
+        (Some(winner), _) => println!("{}", tr!("tournament-winner", name = winner)),
+        (None, Some(pairing)) => println!(
+            "{}",
+            tr!(
+                "tournament-next-game",
+                cross = pairing.cross.as_str(),
+                naught = pairing.naught.as_str()
+            )
+        ),
+        (None, None) => {}
+    }
+}
+
+/// Prints the statistics of the given player from the game database
+#[cfg(feature = "database")]
+fn print_statistics(player: Option<String>) {
+    use first_to_five::persistence::database::{self, GameDatabase, SymbolRecord};
+
+    let player = match player {
+        Some(player) => player,
+        None => {
+            println!("{}", tr!("stats-usage"));
+            return;
+        }
+    };
+    let statistics = database::default_path()
+        .ok_or_else(|| tr!("database-no-place"))
+        .and_then(|path| GameDatabase::open(&path).map_err(|error| error.to_string()))
+        .and_then(|database| {
+            database
+                .statistics(&player, 3)
+                .map_err(|error| error.to_string())
+        });
+    let statistics = match statistics {
+        Ok(statistics) => statistics,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    println!("{}", tr!("stats-title", name = player.as_str()));
+    let print_record = |id: &str, record: &SymbolRecord| match record.win_rate() {
+        Some(win_rate) => println!(
+            "  {}",
+            tr!(
+                id,
+                games = record.games,
+                wins = record.wins,
+                losses = record.losses,
+                win_rate = format!("{:.0}", win_rate * 100.0)
+            )
+        ),
+        None => println!("  {}", tr!(&format!("{}-no-games", id))),
+    };
+    print_record("stats-naughts", &statistics.as_naught);
+    print_record("stats-crosses", &statistics.as_cross);
+    if let Some(length) = statistics.average_game_length {
+        println!(
+            "  {}",
+            tr!("stats-average-length", plays = format!("{:.1}", length))
+        );
+    }
+    if !statistics.opening_squares.is_empty() {
+        let squares: Vec<String> = statistics
+            .opening_squares
+            .iter()
+            .map(|((x, y), times)| {
+                tr!(
+                    "stats-opening-square",
+                    square = format!("{},{}", x, y),
+                    times = *times
+                )
+            })
+            .collect();
+        println!(
+            "  {}",
+            tr!("stats-opening-squares", squares = squares.join(", "))
+        );
+    }
+}
+
+#[cfg(not(feature = "database"))]
+fn print_statistics(_player: Option<String>) {
+    println!("{}", tr!("stats-need-database"));
+}
+
+/// Prints where the given player, or everyone, has played in the first moves
+/// of their games, as a map of play counts around the first play and as a
+/// list with the win rates
+#[cfg(feature = "database")]
+fn print_heatmap(player: Option<String>) {
+    use first_to_five::persistence::database::{self, GameDatabase};
+
+    /// How many moves of each game count as the opening
+    const OPENING_LENGTH: u32 = 6;
+    /// How far from the first play the map reaches
+    const RADIUS: i64 = 4;
+
+    let squares = database::default_path()
+        .ok_or_else(|| tr!("database-no-place"))
+        .and_then(|path| GameDatabase::open(&path).map_err(|error| error.to_string()))
+        .and_then(|database| {
+            database
+                .opening_squares(player.as_deref(), OPENING_LENGTH)
+                .map_err(|error| error.to_string())
+        });
+    let squares = match squares {
+        Ok(squares) => squares,
+        Err(error) => {
+            println!("{}", error);
+            return;
         }
+    };
+    if squares.is_empty() {
+        println!("{}", tr!("heatmap-no-games"));
+        return;
+    }
+
+    match &player {
+        Some(player) => println!("{}", tr!("heatmap-title", name = player.as_str())),
+        None => println!("{}", tr!("heatmap-title-everyone")),
+    }
+    print!("\n    ");
+    for x in -RADIUS..=RADIUS {
+        print!("{:>5}", x);
+    }
+    println!();
+    for y in -RADIUS..=RADIUS {
+        print!("{:>4}", y);
+        for x in -RADIUS..=RADIUS {
+            match squares.iter().find(|square| square.square == (x, y)) {
+                Some(square) => print!("{:>5}", square.plays),
+                None => print!("{:>5}", "."),
+            }
+        }
+        println!();
+    }
+    println!();
+    for square in squares.iter().take(10) {
+        println!(
+            "  {}",
+            tr!(
+                "heatmap-square",
+                square = format!("{},{}", square.square.0, square.square.1),
+                plays = square.plays,
+                win_rate = format!("{:.0}", square.win_rate() * 100.0)
+            )
+        );
+    }
+}
+
+/// Lists the latest games from the game database, optionally only the ones
+/// of a player, against an opponent, with a result or from the last days
+#[cfg(feature = "database")]
+fn print_history(args: Vec<String>) {
+    use first_to_five::persistence::database::{self, GameDatabase};
+    use std::time::SystemTime;
+
+    let filter = match history_filter(&args) {
+        Some(filter) => filter,
+        None => {
+            println!("{}", tr!("history-usage"));
+            return;
+        }
+    };
+    let games = database::default_path()
+        .ok_or_else(|| tr!("database-no-place"))
+        .and_then(|path| GameDatabase::open(&path).map_err(|error| error.to_string()))
+        .and_then(|database| {
+            database
+                .find_games(&filter)
+                .map_err(|error| error.to_string())
+        });
+    let games = match games {
+        Ok(games) => games,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+    if games.is_empty() {
+        println!("{}", tr!("history-no-games"));
+        return;
+    }
+
+    let now = SystemTime::now();
+    for game in &games {
+        let outcome = match game.winner_name() {
+            Some(winner) => tr!("history-won", name = winner),
+            None => tr!("history-no-winner"),
+        };
+        let days = now
+            .duration_since(game.finished_at)
+            .map_or(0, |age| age.as_secs() / SECONDS_PER_DAY);
+        println!(
+            "{}",
+            tr!(
+                "history-game",
+                id = game.id,
+                naught = game.naught.as_str(),
+                cross = game.cross.as_str(),
+                outcome = outcome,
+                plays = game.move_count,
+                days = days
+            )
+        );
+    }
+}
+
+#[cfg(feature = "database")]
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Reads the filter of the history subcommand, or `None` if the arguments
+/// don't make sense
+#[cfg(feature = "database")]
+fn history_filter(args: &[String]) -> Option<first_to_five::persistence::database::GameFilter<'_>> {
+    use first_to_five::persistence::database::{GameFilter, ResultFilter};
+    use std::time::{Duration, SystemTime};
+
+    let mut filter = GameFilter {
+        limit: 20,
+        ..GameFilter::default()
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--against" => filter.opponent = Some(args.next()?.as_str()),
+            "--won" => filter.result = Some(ResultFilter::Won),
+            "--lost" => filter.result = Some(ResultFilter::Lost),
+            "--unfinished" => filter.result = Some(ResultFilter::Unfinished),
+            "--days" => {
+                let days: u64 = args.next()?.parse().ok()?;
+                filter.finished_after = SystemTime::now()
+                    .checked_sub(Duration::from_secs(days.checked_mul(SECONDS_PER_DAY)?));
+            }
+            "--limit" => filter.limit = args.next()?.parse().ok()?,
+            name if filter.player.is_none() && !name.starts_with("--") => {
+                filter.player = Some(name)
+            }
+            _ => return None,
+        }
+    }
+    // Wins and losses are counted from the point of view of a player
+    let needs_player = matches!(filter.result, Some(ResultFilter::Won | ResultFilter::Lost));
+    if needs_player && filter.player.is_none() {
+        return None;
     }
+    Some(filter)
 }
 
-fn start_gui(area: &mut GameArea) {
+#[cfg(not(feature = "database"))]
+fn print_history(_args: Vec<String>) {
+    println!("{}", tr!("stats-need-database"));
+}
+
+/// Prints how often each player wins random playouts from the position, and
+/// the moves which won the most of them
+fn assess(position: &str, rng: &mut StdRng) {
+    /// How many of the best moves are printed
+    const MOVES: usize = 5;
+    /// How many playouts are run after each candidate move
+    const MOVE_PLAYOUTS: u32 = 100;
+
+    if position.trim().is_empty() {
+        println!("{}", tr!("assess-usage"));
+        return;
+    }
+    let area = match GameArea::parse_position(position) {
+        Ok(area) => area,
+        Err(error) => {
+            println!("{}", tr!("paste-failed", error = error.to_string()));
+            return;
+        }
+    };
+    let simulation = Simulation::default();
+    let outcomes = simulation.assess(&area, rng);
+    println!("{}\n", area);
+    println!(
+        "{}",
+        tr!(
+            "assess-outcomes",
+            playouts = outcomes.playouts,
+            cross = format!("{:.0}", outcomes.win_probability(Player::Cross) * 100.0),
+            naught = format!("{:.0}", outcomes.win_probability(Player::Naught) * 100.0),
+            unfinished = outcomes.unfinished()
+        )
+    );
+
+    let player = area.next_player().unwrap_or(Player::Cross);
+    let simulation = Simulation {
+        playouts: MOVE_PLAYOUTS,
+        ..simulation
+    };
+    for candidate in simulation.assess_moves(&area, rng).iter().take(MOVES) {
+        println!(
+            "  {}",
+            tr!(
+                "assess-move",
+                square = format!("{},{}", candidate.spot.0, candidate.spot.1),
+                win_rate = format!("{:.0}", candidate.outcomes.win_probability(player) * 100.0)
+            )
+        );
+    }
+}
+
+#[cfg(not(feature = "database"))]
+fn print_heatmap(_player: Option<String>) {
+    println!("{}", tr!("stats-need-database"));
+}
+
+/// Picks the profiles given with `--profile NAME`, creating the ones which
+/// don't exist yet.
+fn select_profiles() -> Option<Session> {
+    let mut names = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            names.extend(args.next());
+        }
+    }
+    if names.is_empty() {
+        return None;
+    }
+
+    let path = match profiles::default_path() {
+        Some(path) => path,
+        None => {
+            log!("{}", tr!("profiles-no-place"));
+            return None;
+        }
+    };
+    let mut profiles = Profiles::load(&path).unwrap_or_else(|error| {
+        log!("{}", tr!("profiles-unreadable", error = error.to_string()));
+        Profiles::default()
+    });
+    for name in &names {
+        if profiles.get(name).is_none() {
+            log!("{}", tr!("profile-created", name = name.as_str()));
+            profiles
+                .add(Profile::new(name))
+                .expect("The profile shouldn't exist yet");
+        }
+    }
+    if let Err(error) = profiles.save(&path) {
+        log!("{}", error);
+    }
+
+    if let [first, second] = names.as_slice() {
+        match profiles.seat(first, second) {
+            Ok(seating) => {
+                let naught = seating.naught.name.as_str();
+                let cross = seating.cross.name.as_str();
+                let text = match (rating(naught), rating(cross)) {
+                    (Some(naught_rating), Some(cross_rating)) => tr!(
+                        "seating",
+                        naught = naught,
+                        naught_rating = format!("{:.0}", naught_rating),
+                        cross = cross,
+                        cross_rating = format!("{:.0}", cross_rating)
+                    ),
+                    _ => tr!("seating-unrated", naught = naught, cross = cross),
+                };
+                log!("{}", text);
+            }
+            Err(error) => log!("{}", error),
+        }
+    }
+    Some(Session {
+        profiles,
+        path,
+        names,
+    })
+}
+
+/// The name of the achievement in the language of the player
+fn achievement_name(achievement: Achievement) -> String {
+    match achievement {
+        Achievement::FirstWin => tr!("achievement-first-win"),
+        Achievement::DoubleThreatWin => tr!("achievement-double-threat-win"),
+        Achievement::QuickWin => tr!("achievement-quick-win", plays = QUICK_WIN_PLAYS),
+        Achievement::BeatHardestAi => tr!("achievement-beat-hardest-ai"),
+        Achievement::WinningStreak => {
+            tr!("achievement-winning-streak", games = WINNING_STREAK)
+        }
+    }
+}
+
+/// Runs the game window and returns who won, on the board or on time. The
+/// settings are applied again whenever the profile they came from, given as
+/// the profiles file and its name, changes.
+fn start_gui(
+    area: &mut GameArea,
+    settings: ProfileSettings,
+    settings_file: Option<(PathBuf, String)>,
+    names: Option<PlayerNames>,
+    clocks: Option<Clocks>,
+    input: InputMode,
+    journal: Option<&mut Journal>,
+) -> Option<Player> {
     // Change this to OpenGL::V2_1 if not working.
     let opengl = OpenGL::V3_2;
 
@@ -60,9 +959,10 @@ fn start_gui(area: &mut GameArea) {
     };
     drop(temporary_window);
 
-    println!(
+    log!(
         "Resolution: {:?}, scale_factor: {}",
-        resolution, scale_factor
+        resolution,
+        scale_factor
     );
 
     // Create an Glutin window.
@@ -80,12 +980,85 @@ fn start_gui(area: &mut GameArea) {
     .build()
     .unwrap();
 
+    // A replay only clicks on the same spots with the same sizes
+    let (scale_factor, play_size) = match &input {
+        InputMode::Replay(recording) => (recording.start.scale_factor, recording.start.play_size),
+        _ => (scale_factor, settings.play_size),
+    };
+    let mut recorder = match &input {
+        InputMode::Record(path) => {
+            let start = Start {
+                moves: area.moves().copied().collect(),
+                names: names.clone(),
+                time_control: clocks.as_ref().map(Clocks::time_control),
+                play_size,
+                scale_factor,
+            };
+            Recorder::create(path, start)
+                .map_err(|error| log!("{}", tr!("recording-failed", error = error.to_string())))
+                .ok()
+        }
+        _ => None,
+    };
+
     // Create a new game and run it.
-    let app_settings = AppSettings { scale_factor };
-    let mut app = App::new(GlGraphics::new(opengl), area, app_settings);
+    let app_settings = AppSettings {
+        scale_factor,
+        play_size,
+        announce: settings.announce,
+    };
+    let mut app = App::new(GlGraphics::new(opengl), area, app_settings, names, clocks);
+    if let Some(journal) = journal {
+        app.set_journal(journal);
+    }
+    if let (Some(_), InputMode::Record(path)) = (&recorder, &input) {
+        app.set_recording(path.clone());
+    }
+    if let Some((path, name)) = settings_file {
+        app.watch_profile(path, name);
+    }
+
+    let mut title = String::new();
+    if let InputMode::Replay(recording) = input {
+        for e in &recording.events {
+            if window.should_close() {
+                return app.winner();
+            }
+            app.event(e);
+            if let Some(args) = e.update_args() {
+                thread::sleep(Duration::from_secs_f64(args.dt));
+            }
+            if e.render_args().is_some() {
+                window.swap_buffers();
+            }
+            // The window keeps getting events, but only the recorded ones count
+            while window.poll_event().is_some() {}
+            show_title(&app, &mut window, &mut title);
+        }
+        log!("{}", tr!("replay-finished"));
+    }
 
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
+        // Recorded before handling, so that an event crashing the game is in
+        // the recording too
+        if let Some(active_recorder) = &mut recorder {
+            if let Err(error) = active_recorder.record(&e) {
+                log!("{}", tr!("recording-failed", error = error.to_string()));
+                recorder = None;
+            }
+        }
         app.event(&e);
+        show_title(&app, &mut window, &mut title);
+    }
+    app.winner()
+}
+
+/// Shows whose turn it is in the window title, as nothing else draws text
+fn show_title(app: &App, window: &mut Window, title: &mut String) {
+    let new_title = app.title();
+    if new_title != *title {
+        window.set_title(new_title.clone());
+        *title = new_title;
     }
 }
@@ -0,0 +1,1001 @@
+//! A local SQLite database of completed games.
+//!
+//! Every game is stored with the names of its players, the rules, the moves,
+//! the result and when it was played. The query helpers answer the questions
+//! players tend to ask: what did I play lately, how do I fare against this
+//! opponent and which openings win.
+//!
+//! [`GameDatabase::statistics`] sums up how a player has been doing overall,
+//! and [`GameDatabase::opening_squares`] where they tend to play in the
+//! opening.
+//!
+//! Games recorded with [`GameDatabase::record_rated_game`] also update the
+//! Elo ratings of their players, and the ratings after every game are kept
+//! for showing how they have developed.
+//!
+//! The database records the [`SCHEMA_VERSION`] it was created with in
+//! SQLite's `user_version`. Databases created before the schema had a
+//! version have 0 there and are read as version 1.
+use crate::profiles;
+use crate::rating;
+use crate::rules::{
+  coordinate_to_i64, CoordinateOutOfRangeError, GameArea, Player, ReplayError,
+  UnsupportedVersionError, WINNING_LENGTH,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The version of the tables the games are stored in
+pub const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "
+  CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY,
+    naught_player TEXT NOT NULL,
+    cross_player TEXT NOT NULL,
+    winning_length INTEGER NOT NULL,
+    winner TEXT,
+    move_count INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL
+  );
+  CREATE TABLE IF NOT EXISTS moves (
+    game_id INTEGER NOT NULL REFERENCES games (id),
+    move_index INTEGER NOT NULL,
+    player TEXT NOT NULL,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    PRIMARY KEY (game_id, move_index)
+  );
+  CREATE TABLE IF NOT EXISTS ratings (
+    player TEXT NOT NULL,
+    game_id INTEGER NOT NULL REFERENCES games (id),
+    rating REAL NOT NULL,
+    PRIMARY KEY (player, game_id)
+  );
+  CREATE INDEX IF NOT EXISTS games_by_naught ON games (naught_player, finished_at);
+  CREATE INDEX IF NOT EXISTS games_by_cross ON games (cross_player, finished_at);
+";
+
+/// A game to record, with the details the rules don't know about
+#[derive(Debug, Clone)]
+pub struct CompletedGame<'a> {
+  /// Name of the player playing naughts
+  pub naught: &'a str,
+  /// Name of the player playing crosses
+  pub cross: &'a str,
+  pub area: &'a GameArea,
+  /// Who won, with a line on the board or on time
+  pub winner: Option<Player>,
+  pub started_at: SystemTime,
+  pub finished_at: SystemTime,
+}
+
+/// A game as it is listed in the history
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSummary {
+  pub id: i64,
+  pub naught: String,
+  pub cross: String,
+  pub winner: Option<Player>,
+  pub move_count: u32,
+  pub started_at: SystemTime,
+  pub finished_at: SystemTime,
+}
+
+impl GameSummary {
+  /// The name of the player who won the game, if anybody did
+  pub fn winner_name(&self) -> Option<&str> {
+    match self.winner? {
+      Player::Naught => Some(&self.naught),
+      Player::Cross => Some(&self.cross),
+    }
+  }
+}
+
+/// How a game ended for the player being searched for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultFilter {
+  Won,
+  Lost,
+  /// The game ended without a winner
+  Unfinished,
+}
+
+/// Which games to find with [`GameDatabase::find_games`]. Filters which are
+/// `None` match every game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameFilter<'a> {
+  /// Only games this player played in
+  pub player: Option<&'a str>,
+  /// Only games this player played in, usually against `player`
+  pub opponent: Option<&'a str>,
+  /// Only games which ended like this for `player`. Wins and losses need
+  /// `player` to be set.
+  pub result: Option<ResultFilter>,
+  /// Only games which finished at this time or later
+  pub finished_after: Option<SystemTime>,
+  /// Only games which finished before this time
+  pub finished_before: Option<SystemTime>,
+  /// How many games to find at most
+  pub limit: u32,
+}
+
+impl Default for GameFilter<'_> {
+  fn default() -> Self {
+    GameFilter {
+      player: None,
+      opponent: None,
+      result: None,
+      finished_after: None,
+      finished_before: None,
+      limit: 100,
+    }
+  }
+}
+
+/// How two players have fared against each other
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeadToHead {
+  pub wins: u32,
+  pub losses: u32,
+  /// Games which ended without a winner
+  pub unfinished: u32,
+}
+
+/// How the games starting with the same moves have ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningStatistics {
+  /// The opening moves as a move list like `x0,0 o1,0`, moved so that the
+  /// first play is at the origin
+  pub moves: String,
+  pub games: u32,
+  /// Games won by the player who made the first play
+  pub first_player_wins: u32,
+  /// Games won by the other player
+  pub second_player_wins: u32,
+}
+
+/// The opening of a game while collecting [`OpeningStatistics`]
+struct Opening {
+  game_id: i64,
+  winner: Option<Player>,
+  moves: Vec<(Player, i64, i64)>,
+}
+
+/// How a player has fared when playing with one symbol
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolRecord {
+  pub games: u32,
+  pub wins: u32,
+  pub losses: u32,
+}
+
+impl SymbolRecord {
+  /// The share of the games won, if there are any games
+  pub fn win_rate(&self) -> Option<f64> {
+    if self.games == 0 {
+      None
+    } else {
+      Some(f64::from(self.wins) / f64::from(self.games))
+    }
+  }
+}
+
+/// A summary of all the games of a player
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayerStatistics {
+  pub as_naught: SymbolRecord,
+  pub as_cross: SymbolRecord,
+  /// The average number of plays in the games, counting both players
+  pub average_game_length: Option<f64>,
+  /// Where the player made their first play of a game and how many times,
+  /// the most common spots first
+  pub opening_squares: Vec<((i64, i64), u32)>,
+}
+
+/// How often a spot has been played in the opening, relative to the first
+/// play of the game, and how those games ended for whoever played there
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquareStatistics {
+  /// The spot relative to the first play, which is at `(0, 0)`
+  pub square: (i64, i64),
+  pub plays: u32,
+  /// Games won by the player who played on the spot
+  pub wins: u32,
+}
+
+impl SquareStatistics {
+  /// The share of the plays on the spot which led to a win
+  pub fn win_rate(&self) -> f64 {
+    f64::from(self.wins) / f64::from(self.plays)
+  }
+}
+
+/// The rating of a player after a rated game
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingPoint {
+  pub game_id: i64,
+  pub finished_at: SystemTime,
+  pub rating: f64,
+}
+
+/// Error caused by the game database
+#[derive(Debug)]
+pub enum DatabaseError {
+  Sqlite(rusqlite::Error),
+  /// The coordinates are stored as 64-bit integers, and a play was further
+  /// away from the origin than that
+  CoordinateOutOfRange(CoordinateOutOfRangeError),
+  /// A stored game has a player which isn't `Naught` or `Cross`
+  InvalidPlayer(String),
+  /// A stored game breaks the rules, so it can't be loaded
+  Replay(ReplayError),
+  /// The database was created by a newer version of the game
+  Version(UnsupportedVersionError),
+}
+impl std::error::Error for DatabaseError {}
+impl fmt::Display for DatabaseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      DatabaseError::Sqlite(error) => write!(f, "Game database error: {}", error),
+      DatabaseError::CoordinateOutOfRange(error) => write!(f, "{}", error),
+      DatabaseError::InvalidPlayer(player) => write!(f, "Invalid player {:?}", player),
+      DatabaseError::Replay(error) => write!(f, "Invalid stored game: {}", error),
+      DatabaseError::Version(error) => write!(f, "Game database: {}", error),
+    }
+  }
+}
+impl From<rusqlite::Error> for DatabaseError {
+  fn from(error: rusqlite::Error) -> Self {
+    DatabaseError::Sqlite(error)
+  }
+}
+impl From<UnsupportedVersionError> for DatabaseError {
+  fn from(error: UnsupportedVersionError) -> Self {
+    DatabaseError::Version(error)
+  }
+}
+impl From<CoordinateOutOfRangeError> for DatabaseError {
+  fn from(error: CoordinateOutOfRangeError) -> Self {
+    DatabaseError::CoordinateOutOfRange(error)
+  }
+}
+
+fn parse_player(name: &str) -> Result<Player, DatabaseError> {
+  Player::from_name(name).ok_or_else(|| DatabaseError::InvalidPlayer(name.to_owned()))
+}
+
+fn to_unix_seconds(time: SystemTime) -> i64 {
+  match time.duration_since(UNIX_EPOCH) {
+    Ok(duration) => duration.as_secs() as i64,
+    Err(error) => -(error.duration().as_secs() as i64),
+  }
+}
+
+fn from_unix_seconds(seconds: i64) -> SystemTime {
+  if seconds >= 0 {
+    UNIX_EPOCH + Duration::from_secs(seconds as u64)
+  } else {
+    UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs())
+  }
+}
+
+/// Where the game database is stored unless told otherwise: the
+/// `FIRST_TO_FIVE_DATABASE` environment variable, or `games.sqlite3` next to
+/// the player profiles
+pub fn default_path() -> Option<PathBuf> {
+  if let Some(path) = env::var_os("FIRST_TO_FIVE_DATABASE") {
+    return Some(PathBuf::from(path));
+  }
+  profiles::config_directory().map(|directory| directory.join("games.sqlite3"))
+}
+
+fn insert_game(connection: &Connection, game: &CompletedGame) -> Result<i64, DatabaseError> {
+  connection.execute(
+    "INSERT INTO games
+      (naught_player, cross_player, winning_length, winner, move_count, started_at, finished_at)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    params![
+      game.naught,
+      game.cross,
+      WINNING_LENGTH,
+      game.winner.map(Player::name),
+      game.area.moves().count() as i64,
+      to_unix_seconds(game.started_at),
+      to_unix_seconds(game.finished_at),
+    ],
+  )?;
+  let id = connection.last_insert_rowid();
+  let mut insert_move = connection
+    .prepare("INSERT INTO moves (game_id, move_index, player, x, y) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+  for (index, play) in game.area.moves().enumerate() {
+    insert_move.execute(params![
+      id,
+      index as i64,
+      play.player().name(),
+      coordinate_to_i64(play.x())?,
+      coordinate_to_i64(play.y())?,
+    ])?;
+  }
+  Ok(id)
+}
+
+fn current_rating(connection: &Connection, player: &str) -> Result<f64, DatabaseError> {
+  let rating = connection
+    .query_row(
+      "SELECT ratings.rating FROM ratings JOIN games ON games.id = ratings.game_id
+        WHERE ratings.player = ?1
+        ORDER BY games.finished_at DESC, games.id DESC
+        LIMIT 1",
+      params![player],
+      |row| row.get(0),
+    )
+    .optional()?;
+  Ok(rating.unwrap_or(rating::INITIAL_RATING))
+}
+
+/// The game database, see the module documentation
+pub struct GameDatabase {
+  connection: Connection,
+}
+
+impl GameDatabase {
+  /// Opens the database in the given file, creating it if needed
+  pub fn open(path: &Path) -> Result<GameDatabase, DatabaseError> {
+    GameDatabase::with_connection(Connection::open(path)?)
+  }
+
+  /// Opens a database which only lives in memory, mostly for tests
+  pub fn open_in_memory() -> Result<GameDatabase, DatabaseError> {
+    GameDatabase::with_connection(Connection::open_in_memory()?)
+  }
+
+  fn with_connection(connection: Connection) -> Result<GameDatabase, DatabaseError> {
+    let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    // New databases, and those created before the schema had a version,
+    // have 0
+    UnsupportedVersionError::check(version.max(1), SCHEMA_VERSION)?;
+    connection.execute_batch(SCHEMA)?;
+    connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    Ok(GameDatabase { connection })
+  }
+
+  /// Stores a game, returning the id it can be loaded with
+  pub fn record_game(&mut self, game: &CompletedGame) -> Result<i64, DatabaseError> {
+    let transaction = self.connection.transaction()?;
+    let id = insert_game(&transaction, game)?;
+    transaction.commit()?;
+    Ok(id)
+  }
+
+  /// Stores a rated game and updates the ratings of both players, see
+  /// [`crate::rating`]
+  pub fn record_rated_game(&mut self, game: &CompletedGame) -> Result<i64, DatabaseError> {
+    let transaction = self.connection.transaction()?;
+    let id = insert_game(&transaction, game)?;
+    let (naught_rating, cross_rating) = rating::rate_game(
+      current_rating(&transaction, game.naught)?,
+      current_rating(&transaction, game.cross)?,
+      game.winner,
+    );
+    for (player, rating) in &[(game.naught, naught_rating), (game.cross, cross_rating)] {
+      transaction.execute(
+        "INSERT INTO ratings (player, game_id, rating) VALUES (?1, ?2, ?3)",
+        params![player, id, rating],
+      )?;
+    }
+    transaction.commit()?;
+    Ok(id)
+  }
+
+  /// The current rating of the given player
+  pub fn rating(&self, player: &str) -> Result<f64, DatabaseError> {
+    current_rating(&self.connection, player)
+  }
+
+  /// The rating of the given player after each of their rated games, oldest
+  /// first
+  pub fn rating_history(&self, player: &str) -> Result<Vec<RatingPoint>, DatabaseError> {
+    let mut statement = self.connection.prepare(
+      "SELECT ratings.game_id, games.finished_at, ratings.rating
+        FROM ratings JOIN games ON games.id = ratings.game_id
+        WHERE ratings.player = ?1
+        ORDER BY games.finished_at, games.id",
+    )?;
+    let rows = statement.query_map(params![player], |row| {
+      Ok(RatingPoint {
+        game_id: row.get(0)?,
+        finished_at: from_unix_seconds(row.get(1)?),
+        rating: row.get(2)?,
+      })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+  }
+
+  /// Loads a stored game by replaying its moves
+  pub fn load_game(&self, id: i64) -> Result<Option<GameArea>, DatabaseError> {
+    let winning_length = self
+      .connection
+      .query_row(
+        "SELECT winning_length FROM games WHERE id = ?1",
+        params![id],
+        |row| row.get(0),
+      )
+      .optional()?;
+    let winning_length = match winning_length {
+      Some(winning_length) => winning_length,
+      None => return Ok(None),
+    };
+    // The stored winner may have won on time, so only the plays are checked
+    let area = GameArea::replay(winning_length, self.moves(id)?).map_err(DatabaseError::Replay)?;
+    Ok(Some(area))
+  }
+
+  fn moves(&self, id: i64) -> Result<Vec<(Player, i128, i128)>, DatabaseError> {
+    let mut statement = self
+      .connection
+      .prepare("SELECT player, x, y FROM moves WHERE game_id = ?1 ORDER BY move_index")?;
+    let rows = statement.query_map(params![id], |row| {
+      Ok((
+        row.get::<_, String>(0)?,
+        row.get::<_, i64>(1)?,
+        row.get::<_, i64>(2)?,
+      ))
+    })?;
+    let mut moves = vec![];
+    for row in rows {
+      let (player, x, y) = row?;
+      moves.push((parse_player(&player)?, x as i128, y as i128));
+    }
+    Ok(moves)
+  }
+
+  /// The latest games the given player has played, newest first
+  pub fn history(&self, player: &str, limit: u32) -> Result<Vec<GameSummary>, DatabaseError> {
+    self.find_games(&GameFilter {
+      player: Some(player),
+      limit,
+      ..GameFilter::default()
+    })
+  }
+
+  /// The games matching the filter, newest first
+  pub fn find_games(&self, filter: &GameFilter) -> Result<Vec<GameSummary>, DatabaseError> {
+    let mut statement = self.connection.prepare(
+      "SELECT id, naught_player, cross_player, winner, move_count, started_at, finished_at
+        FROM games
+        WHERE (?1 IS NULL OR naught_player = ?1 OR cross_player = ?1)
+          AND (?2 IS NULL OR naught_player = ?2 OR cross_player = ?2)
+          AND (?3 IS NULL OR finished_at >= ?3)
+          AND (?4 IS NULL OR finished_at < ?4)
+          AND (?5 IS NULL
+            OR (?5 = 'unfinished' AND winner IS NULL)
+            OR (?5 = 'won' AND ((naught_player = ?1 AND winner = 'Naught')
+              OR (cross_player = ?1 AND winner = 'Cross')))
+            OR (?5 = 'lost' AND ((naught_player = ?1 AND winner = 'Cross')
+              OR (cross_player = ?1 AND winner = 'Naught'))))
+        ORDER BY finished_at DESC, id DESC
+        LIMIT ?6",
+    )?;
+    let result = filter.result.map(|result| match result {
+      ResultFilter::Won => "won",
+      ResultFilter::Lost => "lost",
+      ResultFilter::Unfinished => "unfinished",
+    });
+    let parameters = params![
+      filter.player,
+      filter.opponent,
+      filter.finished_after.map(to_unix_seconds),
+      filter.finished_before.map(to_unix_seconds),
+      result,
+      filter.limit,
+    ];
+    let rows = statement.query_map(parameters, |row| {
+      Ok((
+        row.get::<_, i64>(0)?,
+        row.get::<_, String>(1)?,
+        row.get::<_, String>(2)?,
+        row.get::<_, Option<String>>(3)?,
+        row.get::<_, u32>(4)?,
+        row.get::<_, i64>(5)?,
+        row.get::<_, i64>(6)?,
+      ))
+    })?;
+    let mut games = vec![];
+    for row in rows {
+      let (id, naught, cross, winner, move_count, started_at, finished_at) = row?;
+      games.push(GameSummary {
+        id,
+        naught,
+        cross,
+        winner: winner.as_deref().map(parse_player).transpose()?,
+        move_count,
+        started_at: from_unix_seconds(started_at),
+        finished_at: from_unix_seconds(finished_at),
+      });
+    }
+    Ok(games)
+  }
+
+  /// The record of `player` in the games played against `opponent`
+  pub fn head_to_head(&self, player: &str, opponent: &str) -> Result<HeadToHead, DatabaseError> {
+    let mut statement = self.connection.prepare(
+      "SELECT naught_player, winner FROM games
+        WHERE (naught_player = ?1 AND cross_player = ?2)
+          OR (naught_player = ?2 AND cross_player = ?1)",
+    )?;
+    let rows = statement.query_map(params![player, opponent], |row| {
+      Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    })?;
+    let mut record = HeadToHead::default();
+    for row in rows {
+      let (naught, winner) = row?;
+      let side = if naught == player {
+        Player::Naught
+      } else {
+        Player::Cross
+      };
+      match winner.as_deref().map(parse_player).transpose()? {
+        None => record.unfinished += 1,
+        Some(winner) if winner == side => record.wins += 1,
+        Some(_) => record.losses += 1,
+      }
+    }
+    Ok(record)
+  }
+
+  /// Sums up the games of the given player, with at most `squares` of their
+  /// favourite opening squares
+  pub fn statistics(&self, player: &str, squares: u32) -> Result<PlayerStatistics, DatabaseError> {
+    let mut statement = self.connection.prepare(
+      "SELECT naught_player, winner, move_count FROM games
+        WHERE naught_player = ?1 OR cross_player = ?1",
+    )?;
+    let rows = statement.query_map(params![player], |row| {
+      Ok((
+        row.get::<_, String>(0)?,
+        row.get::<_, Option<String>>(1)?,
+        row.get::<_, u32>(2)?,
+      ))
+    })?;
+    let mut statistics = PlayerStatistics::default();
+    let mut total_moves = 0;
+    for row in rows {
+      let (naught, winner, move_count) = row?;
+      let (side, record) = if naught == player {
+        (Player::Naught, &mut statistics.as_naught)
+      } else {
+        (Player::Cross, &mut statistics.as_cross)
+      };
+      record.games += 1;
+      match winner.as_deref().map(parse_player).transpose()? {
+        Some(winner) if winner == side => record.wins += 1,
+        Some(_) => record.losses += 1,
+        None => {}
+      }
+      total_moves += u64::from(move_count);
+    }
+    let games = statistics.as_naught.games + statistics.as_cross.games;
+    if games > 0 {
+      statistics.average_game_length = Some(total_moves as f64 / f64::from(games));
+    }
+
+    let mut statement = self.connection.prepare(
+      "SELECT moves.x, moves.y, COUNT(*) AS times
+        FROM moves JOIN games ON games.id = moves.game_id
+        WHERE (games.naught_player = ?1 OR games.cross_player = ?1)
+          AND moves.move_index < 2
+          AND moves.player = CASE WHEN games.naught_player = ?1 THEN 'Naught' ELSE 'Cross' END
+        GROUP BY moves.x, moves.y
+        ORDER BY times DESC, moves.x, moves.y
+        LIMIT ?2",
+    )?;
+    let rows = statement.query_map(params![player, squares], |row| {
+      Ok(((row.get(0)?, row.get(1)?), row.get(2)?))
+    })?;
+    statistics.opening_squares = rows.collect::<Result<_, _>>()?;
+    Ok(statistics)
+  }
+
+  /// Counts where the first `length` moves of the games were played relative
+  /// to the first play, and how often the player who played there won. With
+  /// a player given, only their own plays are counted. The most played spots
+  /// come first.
+  pub fn opening_squares(
+    &self,
+    player: Option<&str>,
+    length: u32,
+  ) -> Result<Vec<SquareStatistics>, DatabaseError> {
+    let mut statement = self.connection.prepare(
+      "SELECT games.id, games.winner, games.naught_player, games.cross_player,
+          moves.player, moves.x, moves.y
+        FROM games JOIN moves ON moves.game_id = games.id
+        WHERE moves.move_index < ?1
+        ORDER BY games.id, moves.move_index",
+    )?;
+    let rows = statement.query_map(params![length], |row| {
+      Ok((
+        row.get::<_, i64>(0)?,
+        row.get::<_, Option<String>>(1)?,
+        row.get::<_, String>(2)?,
+        row.get::<_, String>(3)?,
+        row.get::<_, String>(4)?,
+        row.get::<_, i64>(5)?,
+        row.get::<_, i64>(6)?,
+      ))
+    })?;
+
+    let mut squares: BTreeMap<(i64, i64), SquareStatistics> = BTreeMap::new();
+    // The game being counted and its first play
+    let mut first_play: Option<(i64, i64, i64)> = None;
+    for row in rows {
+      let (id, winner, naught, cross, mover, x, y) = row?;
+      let (origin_x, origin_y) = match first_play {
+        Some((game_id, origin_x, origin_y)) if game_id == id => (origin_x, origin_y),
+        _ => {
+          first_play = Some((id, x, y));
+          (x, y)
+        }
+      };
+      let mover = parse_player(&mover)?;
+      let name = match mover {
+        Player::Naught => &naught,
+        Player::Cross => &cross,
+      };
+      if player.is_some_and(|player| player != name.as_str()) {
+        continue;
+      }
+      let square = (x - origin_x, y - origin_y);
+      let statistics = squares.entry(square).or_insert(SquareStatistics {
+        square,
+        plays: 0,
+        wins: 0,
+      });
+      statistics.plays += 1;
+      if winner.as_deref().map(parse_player).transpose()? == Some(mover) {
+        statistics.wins += 1;
+      }
+    }
+
+    let mut squares: Vec<SquareStatistics> = squares.into_values().collect();
+    squares.sort_by_key(|square| Reverse(square.plays));
+    Ok(squares)
+  }
+
+  /// Groups the games by their first `length` moves and counts how the
+  /// games of each opening ended, the most played openings first. Openings
+  /// are compared regardless of where on the board they were played.
+  pub fn opening_statistics(&self, length: u32) -> Result<Vec<OpeningStatistics>, DatabaseError> {
+    let mut statement = self.connection.prepare(
+      "SELECT games.id, games.winner, moves.player, moves.x, moves.y
+        FROM games JOIN moves ON moves.game_id = games.id
+        WHERE moves.move_index < ?1 AND games.move_count >= ?1
+        ORDER BY games.id, moves.move_index",
+    )?;
+    let rows = statement.query_map(params![length], |row| {
+      Ok((
+        row.get::<_, i64>(0)?,
+        row.get::<_, Option<String>>(1)?,
+        row.get::<_, String>(2)?,
+        row.get::<_, i64>(3)?,
+        row.get::<_, i64>(4)?,
+      ))
+    })?;
+
+    // The opening of each game, in the order of the games
+    let mut games: Vec<Opening> = vec![];
+    for row in rows {
+      let (id, winner, player, x, y) = row?;
+      let play = (parse_player(&player)?, x, y);
+      match games.last_mut() {
+        Some(opening) if opening.game_id == id => opening.moves.push(play),
+        _ => games.push(Opening {
+          game_id: id,
+          winner: winner.as_deref().map(parse_player).transpose()?,
+          moves: vec![play],
+        }),
+      }
+    }
+
+    let mut openings: BTreeMap<String, OpeningStatistics> = BTreeMap::new();
+    for Opening { winner, moves, .. } in games {
+      let (first_player, origin_x, origin_y) = moves[0];
+      let key = moves
+        .iter()
+        .map(|&(player, x, y)| {
+          let symbol = match player {
+            Player::Naught => 'o',
+            Player::Cross => 'x',
+          };
+          format!("{}{},{}", symbol, x - origin_x, y - origin_y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+      let statistics = openings
+        .entry(key.clone())
+        .or_insert_with(|| OpeningStatistics {
+          moves: key,
+          games: 0,
+          first_player_wins: 0,
+          second_player_wins: 0,
+        });
+      statistics.games += 1;
+      match winner {
+        Some(winner) if winner == first_player => statistics.first_player_wins += 1,
+        Some(_) => statistics.second_player_wins += 1,
+        None => {}
+      }
+    }
+
+    let mut openings: Vec<OpeningStatistics> = openings.into_values().collect();
+    openings.sort_by_key(|opening| Reverse(opening.games));
+    Ok(openings)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn game(moves: &str) -> GameArea {
+    GameArea::from_move_list(moves).unwrap()
+  }
+
+  fn record(
+    database: &mut GameDatabase,
+    naught: &str,
+    cross: &str,
+    area: &GameArea,
+    at: u64,
+  ) -> i64 {
+    let finished_at = UNIX_EPOCH + Duration::from_secs(at);
+    database
+      .record_game(&CompletedGame {
+        naught,
+        cross,
+        area,
+        winner: area.winner(),
+        started_at: finished_at - Duration::from_secs(60),
+        finished_at,
+      })
+      .unwrap()
+  }
+
+  const CROSS_WINS: &str = "x0,0 o0,1 x1,0 o1,1 x2,0 o2,1 x3,0 o3,1 x4,0";
+
+  #[test]
+  fn test_record_and_load() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    let area = game(CROSS_WINS);
+    let id = record(&mut database, "Aino", "Eero", &area, 1000);
+
+    let loaded = database.load_game(id).unwrap().unwrap();
+    assert_eq!(loaded.to_json(), area.to_json());
+    assert!(database.load_game(id + 1).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_schema_version() {
+    let database = GameDatabase::open_in_memory().unwrap();
+    let version: u32 = database
+      .connection
+      .pragma_query_value(None, "user_version", |row| row.get(0))
+      .unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+
+    let newer = Connection::open_in_memory().unwrap();
+    newer
+      .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+      .unwrap();
+    assert!(matches!(
+      GameDatabase::with_connection(newer),
+      Err(DatabaseError::Version(UnsupportedVersionError {
+        version,
+        latest: SCHEMA_VERSION,
+      })) if version == SCHEMA_VERSION + 1
+    ));
+  }
+
+  #[test]
+  fn test_history_and_head_to_head() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    record(&mut database, "Aino", "Eero", &game(CROSS_WINS), 1000);
+    record(&mut database, "Eero", "Aino", &game(CROSS_WINS), 2000);
+    record(&mut database, "Aino", "Eero", &game("x0,0 o5,5"), 3000);
+    record(&mut database, "Eero", "Ville", &game(CROSS_WINS), 4000);
+
+    let history = database.history("Aino", 2).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(
+      history[0].finished_at,
+      UNIX_EPOCH + Duration::from_secs(3000)
+    );
+    assert_eq!(history[0].winner, None);
+    assert_eq!(history[1].winner_name(), Some("Aino"));
+
+    assert_eq!(
+      database.head_to_head("Aino", "Eero").unwrap(),
+      HeadToHead {
+        wins: 1,
+        losses: 1,
+        unfinished: 1
+      }
+    );
+  }
+
+  #[test]
+  fn test_find_games() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    record(&mut database, "Aino", "Eero", &game(CROSS_WINS), 1000);
+    record(&mut database, "Eero", "Aino", &game(CROSS_WINS), 2000);
+    record(&mut database, "Aino", "Ville", &game(CROSS_WINS), 3000);
+    record(&mut database, "Aino", "Eero", &game("x0,0 o5,5"), 4000);
+
+    let find = |filter: GameFilter| -> Vec<i64> {
+      let games = database.find_games(&filter).unwrap();
+      games.iter().map(|game| game.id).collect()
+    };
+    let aino = GameFilter {
+      player: Some("Aino"),
+      ..GameFilter::default()
+    };
+    assert_eq!(find(aino.clone()), vec![4, 3, 2, 1]);
+    assert_eq!(
+      find(GameFilter {
+        opponent: Some("Eero"),
+        ..aino.clone()
+      }),
+      vec![4, 2, 1]
+    );
+    assert_eq!(
+      find(GameFilter {
+        result: Some(ResultFilter::Lost),
+        ..aino.clone()
+      }),
+      vec![3, 1]
+    );
+    assert_eq!(
+      find(GameFilter {
+        result: Some(ResultFilter::Unfinished),
+        ..GameFilter::default()
+      }),
+      vec![4]
+    );
+    assert_eq!(
+      find(GameFilter {
+        finished_after: Some(UNIX_EPOCH + Duration::from_secs(2000)),
+        finished_before: Some(UNIX_EPOCH + Duration::from_secs(4000)),
+        limit: 1,
+        ..aino
+      }),
+      vec![3]
+    );
+  }
+
+  #[test]
+  fn test_ratings() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    assert_eq!(database.rating("Aino").unwrap(), rating::INITIAL_RATING);
+
+    let area = game(CROSS_WINS);
+    for (at, (naught, cross)) in [("Aino", "Eero"), ("Eero", "Aino")].iter().enumerate() {
+      let finished_at = UNIX_EPOCH + Duration::from_secs(at as u64 * 1000);
+      database
+        .record_rated_game(&CompletedGame {
+          naught,
+          cross,
+          area: &area,
+          winner: area.winner(),
+          started_at: finished_at,
+          finished_at,
+        })
+        .unwrap();
+    }
+    // Unrated games don't change the ratings
+    record(&mut database, "Aino", "Eero", &area, 3000);
+
+    let history = database.rating_history("Eero").unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].rating, 1516.0);
+    assert!(history[1].rating < history[0].rating);
+    assert_eq!(database.rating("Eero").unwrap(), history[1].rating);
+    // Whatever one player gains, the other loses
+    let total = database.rating("Aino").unwrap() + database.rating("Eero").unwrap();
+    assert!((total - 2.0 * rating::INITIAL_RATING).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_statistics() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    record(&mut database, "Aino", "Eero", &game(CROSS_WINS), 1000);
+    record(&mut database, "Eero", "Aino", &game(CROSS_WINS), 2000);
+    record(&mut database, "Aino", "Eero", &game("x1,1 o5,5"), 3000);
+
+    let statistics = database.statistics("Aino", 5).unwrap();
+    assert_eq!(
+      statistics.as_naught,
+      SymbolRecord {
+        games: 2,
+        wins: 0,
+        losses: 1
+      }
+    );
+    assert_eq!(statistics.as_cross.win_rate(), Some(1.0));
+    assert_eq!(statistics.average_game_length, Some(20.0 / 3.0));
+    assert_eq!(
+      statistics.opening_squares,
+      vec![((0, 0), 1), ((0, 1), 1), ((5, 5), 1)]
+    );
+
+    assert_eq!(
+      database.statistics("Ville", 5).unwrap(),
+      PlayerStatistics::default()
+    );
+  }
+
+  #[test]
+  fn test_opening_squares() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    record(&mut database, "Aino", "Eero", &game(CROSS_WINS), 1000);
+    record(&mut database, "Aino", "Eero", &game("x10,10 o10,11"), 2000);
+    record(&mut database, "Eero", "Aino", &game("x5,5 o6,6"), 3000);
+
+    let squares = database.opening_squares(None, 2).unwrap();
+    assert_eq!(
+      squares,
+      vec![
+        SquareStatistics {
+          square: (0, 0),
+          plays: 3,
+          wins: 1
+        },
+        SquareStatistics {
+          square: (0, 1),
+          plays: 2,
+          wins: 0
+        },
+        SquareStatistics {
+          square: (1, 1),
+          plays: 1,
+          wins: 0
+        },
+      ]
+    );
+    assert_eq!(squares[0].win_rate(), 1.0 / 3.0);
+
+    // Aino played naughts in the first two games and crosses in the last
+    let squares = database.opening_squares(Some("Aino"), 2).unwrap();
+    let squares: Vec<_> = squares
+      .iter()
+      .map(|square| (square.square, square.plays))
+      .collect();
+    assert_eq!(squares, vec![((0, 1), 2), ((0, 0), 1)]);
+  }
+
+  #[test]
+  fn test_opening_statistics() {
+    let mut database = GameDatabase::open_in_memory().unwrap();
+    record(&mut database, "Aino", "Eero", &game(CROSS_WINS), 1000);
+    // The same opening played elsewhere on the board
+    record(&mut database, "Aino", "Eero", &game("x10,10 o10,11"), 2000);
+    record(&mut database, "Aino", "Eero", &game("o0,0 x1,1"), 3000);
+
+    let openings = database.opening_statistics(2).unwrap();
+    assert_eq!(
+      openings[0],
+      OpeningStatistics {
+        moves: String::from("x0,0 o0,1"),
+        games: 2,
+        first_player_wins: 1,
+        second_player_wins: 0,
+      }
+    );
+    assert_eq!(openings[1].moves, "o0,0 x1,1");
+  }
+}
@@ -0,0 +1,233 @@
+//! A write-ahead journal of the game being played.
+//!
+//! Every play is appended to the journal, and flushed to the disk, before it
+//! is made on the board. If the game crashes, or the computer loses power,
+//! the journal still has every play the players saw, and
+//! [`recover`] sets the game up again from it. A game which ends normally
+//! removes its journal with [`Journal::finish`].
+//!
+//! The journal starts with a line telling the [`JOURNAL_FORMAT_VERSION`] it
+//! was written in and the position the game started from, followed by a
+//! play per line as JSON. A line cut short by a crash in the middle of
+//! writing it is left out when recovering. Journals of format version 1 have
+//! no version line, and journals of version 2 have the starting position as
+//! plays of their own.
+use crate::profiles;
+use crate::rules::{GameArea, Play, Player, UnsupportedVersionError};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The version of the format the journal is written in
+pub const JOURNAL_FORMAT_VERSION: u32 = 3;
+
+/// Where the journal is kept unless told otherwise: the
+/// `FIRST_TO_FIVE_JOURNAL` environment variable, or `journal.jsonl` in the
+/// configuration directory of the user
+pub fn default_path() -> Option<PathBuf> {
+  if let Some(path) = env::var_os("FIRST_TO_FIVE_JOURNAL") {
+    return Some(PathBuf::from(path));
+  }
+  profiles::config_directory().map(|directory| directory.join("journal.jsonl"))
+}
+
+/// Sets up the game of a journal left behind by a game which didn't end
+/// normally. A missing or empty journal, or one of a game which was already
+/// won, has no game to recover.
+///
+/// The starting position is set up as it was, even with plays out of turn
+/// like in a scenario or a pasted template. The plays after it are made
+/// again one by one until the first line which can't be read or played, as
+/// anything after it was written after the crash began.
+pub fn recover(path: &Path) -> io::Result<Option<GameArea>> {
+  let text = match fs::read_to_string(path) {
+    Ok(text) => text,
+    Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+    Err(error) => return Err(error),
+  };
+  let mut area = GameArea::default();
+  // Only whole lines were written completely
+  let whole_lines = text.rsplit_once('\n').map_or("", |(whole, _)| whole);
+  let mut lines = whole_lines.lines().peekable();
+  if let Some(Ok(header)) = lines
+    .peek()
+    .map(|line| serde_json::from_str::<Header>(line))
+  {
+    UnsupportedVersionError::check(header.version, JOURNAL_FORMAT_VERSION)
+      .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    lines.next();
+    // Version 2 wrote the starting position as plays after the header
+    let start = match header.version {
+      2 => vec![],
+      _ => header.start.unwrap_or_default(),
+    };
+    for play in start {
+      if area.mark(play.player(), play.x(), play.y()).is_err() {
+        break;
+      }
+    }
+  }
+  for line in lines {
+    let play: Play = match serde_json::from_str(line) {
+      Ok(play) => play,
+      Err(_) => break,
+    };
+    if area.play(play.player(), play.x(), play.y()).is_err() {
+      break;
+    }
+  }
+  // A finished game has nothing left to play
+  let unfinished = area.moves().next().is_some() && area.winner().is_none();
+  Ok(Some(area).filter(|_| unfinished))
+}
+
+/// The first line of a journal
+#[derive(Serialize, Deserialize)]
+struct Header {
+  version: u32,
+  /// The plays of the position the game started from, in the order they
+  /// were set up. Version 2 has none.
+  #[serde(default)]
+  start: Option<Vec<Play>>,
+}
+
+/// A play as it is written in the journal, the same way as [`Play`]
+#[derive(Serialize)]
+struct JournalPlay {
+  x: i128,
+  y: i128,
+  player: Player,
+}
+
+/// The journal of the game being played
+pub struct Journal {
+  file: File,
+  path: PathBuf,
+}
+
+impl Journal {
+  /// Starts a new journal in place of any old one, with the plays already
+  /// made in the game
+  pub fn create(path: &Path, area: &GameArea) -> io::Result<Journal> {
+    if let Some(directory) = path.parent() {
+      fs::create_dir_all(directory)?;
+    }
+    let file = OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .open(path)?;
+    let mut journal = Journal {
+      file,
+      path: path.to_owned(),
+    };
+    journal.write(&Header {
+      version: JOURNAL_FORMAT_VERSION,
+      start: Some(area.moves().copied().collect()),
+    })?;
+    journal.file.sync_data()?;
+    Ok(journal)
+  }
+
+  fn write(&mut self, play: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_string(play)
+      .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+    line.push('\n');
+    self.file.write_all(line.as_bytes())
+  }
+
+  /// Writes the play to the disk. Make the play only after this succeeds.
+  pub fn append(&mut self, player: Player, x: i128, y: i128) -> io::Result<()> {
+    self.write(&JournalPlay { x, y, player })?;
+    self.file.sync_data()
+  }
+
+  /// Starts the journal over with another game, like a pasted position
+  pub fn restart(&mut self, area: &GameArea) -> io::Result<()> {
+    *self = Journal::create(&self.path, area)?;
+    Ok(())
+  }
+
+  /// Removes the journal of a game which ended normally
+  pub fn finish(self) -> io::Result<()> {
+    drop(self.file);
+    fs::remove_file(&self.path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::Player;
+
+  fn moves(area: &GameArea) -> Vec<Play> {
+    area.moves().copied().collect()
+  }
+
+  #[test]
+  fn test_recover() {
+    let path = env::temp_dir().join("first-to-five-test-journal.jsonl");
+    let area = GameArea::from_move_list("x0,0 o1,1").unwrap();
+    let mut journal = Journal::create(&path, &area).unwrap();
+    let mut played = area.clone();
+    journal.append(Player::Cross, 2, 2).unwrap();
+    played.play(Player::Cross, 2, 2).unwrap();
+
+    let recovered = recover(&path).unwrap().unwrap();
+    assert_eq!(moves(&recovered), moves(&played));
+
+    // A play cut short while writing it is left out
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"{\"player\":\"Na").unwrap();
+    let recovered = recover(&path).unwrap().unwrap();
+    assert_eq!(moves(&recovered), moves(&played));
+
+    journal.finish().unwrap();
+    assert!(recover(&path).unwrap().is_none());
+
+    // Journals without a version are from the first version
+    fs::write(&path, "{\"x\":0,\"y\":0,\"player\":\"Cross\"}\n").unwrap();
+    assert_eq!(recover(&path).unwrap().unwrap().moves().count(), 1);
+    fs::write(&path, "{\"version\":99}\n").unwrap();
+    assert!(recover(&path).is_err());
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_recover_version_2() {
+    let path = env::temp_dir().join("first-to-five-test-journal-version-2.jsonl");
+    // The header of version 2 had only the version, and the starting
+    // position was written as plays
+    fs::write(
+      &path,
+      "{\"version\":2}\n\
+       {\"x\":0,\"y\":0,\"player\":\"Cross\"}\n\
+       {\"x\":1,\"y\":1,\"player\":\"Naught\"}\n",
+    )
+    .unwrap();
+    let recovered = recover(&path).unwrap().unwrap();
+    assert_eq!(
+      moves(&recovered),
+      moves(&GameArea::from_move_list("x0,0 o1,1").unwrap())
+    );
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_recover_out_of_turn_start() {
+    let path = env::temp_dir().join("first-to-five-test-journal-start.jsonl");
+    // Scenarios and pasted templates may have plays out of turn
+    let mut area = GameArea::default();
+    area.mark(Player::Cross, 0, 0).unwrap();
+    area.mark(Player::Cross, 1, 0).unwrap();
+    let mut journal = Journal::create(&path, &area).unwrap();
+    journal.append(Player::Naught, 5, 5).unwrap();
+    area.play(Player::Naught, 5, 5).unwrap();
+
+    let recovered = recover(&path).unwrap().unwrap();
+    assert_eq!(moves(&recovered), moves(&area));
+    journal.finish().unwrap();
+  }
+}
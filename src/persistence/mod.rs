@@ -0,0 +1,263 @@
+//! Saving games to files and loading them back.
+//!
+//! Games can be stored either as the JSON interchange format, which is
+//! readable and documented, or as a compact bincode encoding which is a lot
+//! smaller and faster to read and write. `cargo bench` compares the two.
+//!
+//! Both formats record the [`FORMAT_VERSION`] they were saved in. Games
+//! saved by older versions of the game are migrated when they are loaded, and
+//! games saved by newer ones are refused with an [`UnsupportedVersionError`].
+use crate::rules::{
+  FromJsonError, GameArea, Play, Player, ReplayError, UnsupportedVersionError, FORMAT_VERSION,
+  WINNING_LENGTH,
+};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::{self, Utf8Error};
+
+#[cfg(feature = "database")]
+pub mod database;
+pub mod journal;
+
+/// The encodings a game can be saved in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  /// The JSON interchange format of [`GameArea::to_json`]
+  Json,
+  /// A compact binary encoding
+  Bincode,
+}
+
+impl Format {
+  /// Picks the format by the extension of the file name: `.json` is JSON,
+  /// and anything else is bincode.
+  pub fn from_path(path: &Path) -> Format {
+    match path.extension().and_then(|extension| extension.to_str()) {
+      Some("json") => Format::Json,
+      _ => Format::Bincode,
+    }
+  }
+
+  /// The file name extension for files in this format
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Format::Json => "json",
+      Format::Bincode => "ftf",
+    }
+  }
+}
+
+/// A whole game in the shape it is encoded with bincode. Bincode can't encode
+/// the tagged result of the JSON format, so this has its own shape.
+#[derive(Serialize, Deserialize)]
+struct BinaryRecord {
+  winning_length: i32,
+  moves: Vec<Play>,
+  winner: Option<Player>,
+}
+
+/// What games saved with bincode start with, followed by the format version
+/// as four little-endian bytes. Games saved before format version 2 start
+/// right away with the record.
+const BINARY_MAGIC: &[u8] = b"FTF";
+
+/// Bincode with variable length integers, so that the 128-bit coordinates of
+/// plays near the origin take a byte each
+fn bincode_options() -> impl Options {
+  bincode::DefaultOptions::new()
+}
+
+/// Error caused by trying to load a game which isn't valid
+#[derive(Debug)]
+pub enum LoadError {
+  Io(io::Error),
+  Json(FromJsonError),
+  /// A game saved as JSON isn't valid UTF-8
+  Utf8(Utf8Error),
+  Bincode(bincode::Error),
+  /// A game saved with bincode by a newer version of the game. JSON games
+  /// tell it with [`FromJsonError::UnsupportedVersion`].
+  Version(UnsupportedVersionError),
+  /// The game breaks the rules
+  Replay(ReplayError),
+}
+impl std::error::Error for LoadError {}
+impl fmt::Display for LoadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      LoadError::Io(error) => write!(f, "Couldn't read the game: {}", error),
+      LoadError::Json(error) => write!(f, "{}", error),
+      LoadError::Utf8(error) => write!(f, "Invalid saved game: {}", error),
+      LoadError::Bincode(error) => write!(f, "Invalid saved game: {}", error),
+      LoadError::Version(error) => write!(f, "{}", error),
+      LoadError::Replay(error) => write!(f, "{}", error),
+    }
+  }
+}
+impl From<io::Error> for LoadError {
+  fn from(error: io::Error) -> Self {
+    LoadError::Io(error)
+  }
+}
+
+/// Encodes the whole game in the given format
+pub fn save(area: &GameArea, format: Format) -> Vec<u8> {
+  match format {
+    Format::Json => area.to_json().into_bytes(),
+    Format::Bincode => {
+      let record = BinaryRecord {
+        winning_length: WINNING_LENGTH,
+        moves: area.moves().copied().collect(),
+        winner: area.winner(),
+      };
+      let mut bytes = BINARY_MAGIC.to_vec();
+      bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+      // Encoding plain structs into memory can't fail
+      bytes.extend(bincode_options().serialize(&record).unwrap());
+      bytes
+    }
+  }
+}
+
+/// The format version of a game saved with bincode, and the bytes of the
+/// record after the header
+fn binary_version(bytes: &[u8]) -> (u32, &[u8]) {
+  match bytes.strip_prefix(BINARY_MAGIC) {
+    Some(rest) if rest.len() >= 4 => {
+      let (version, record) = rest.split_at(4);
+      let version = u32::from_le_bytes([version[0], version[1], version[2], version[3]]);
+      (version, record)
+    }
+    _ => (1, bytes),
+  }
+}
+
+/// Decodes a game encoded with [`save`] in the given format. The moves are
+/// replayed, so a game which breaks the rules is refused.
+pub fn load(bytes: &[u8], format: Format) -> Result<GameArea, LoadError> {
+  match format {
+    Format::Json => {
+      let json = str::from_utf8(bytes).map_err(LoadError::Utf8)?;
+      GameArea::from_json(json).map_err(LoadError::Json)
+    }
+    Format::Bincode => {
+      let (version, bytes) = binary_version(bytes);
+      UnsupportedVersionError::check(version, FORMAT_VERSION).map_err(LoadError::Version)?;
+      // The record has kept its shape since version 1, so there is nothing
+      // to migrate yet
+      let record: BinaryRecord = bincode_options()
+        .deserialize(bytes)
+        .map_err(LoadError::Bincode)?;
+      let plays = record
+        .moves
+        .iter()
+        .map(|play| (play.player(), play.x(), play.y()));
+      GameArea::replay_to_result(record.winning_length, plays, record.winner)
+        .map_err(LoadError::Replay)
+    }
+  }
+}
+
+/// Saves the game to a file, in the format its extension calls for
+pub fn save_to_file(area: &GameArea, path: &Path) -> io::Result<()> {
+  fs::write(path, save(area, Format::from_path(path)))
+}
+
+/// Loads a game saved with [`save_to_file`]
+pub fn load_from_file(path: &Path) -> Result<GameArea, LoadError> {
+  let bytes = fs::read(path)?;
+  load(&bytes, Format::from_path(path))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::IllegalPlayError;
+  use crate::strategy::{game_area, DEFAULT_COORDINATES};
+  use proptest::prelude::*;
+
+  proptest! {
+    #[test]
+    fn test_round_trip(area in game_area(60, DEFAULT_COORDINATES)) {
+      for &format in &[Format::Json, Format::Bincode] {
+        let loaded = load(&save(&area, format), format).unwrap();
+        prop_assert_eq!(loaded.to_json(), area.to_json());
+      }
+    }
+  }
+
+  #[test]
+  fn test_bincode_is_smaller() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, 1)?;
+    }
+    assert!(save(&area, Format::Bincode).len() * 4 < save(&area, Format::Json).len());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_versions() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    area.play(Player::Cross, 0, 0)?;
+    area.play(Player::Naught, 1, 1)?;
+
+    // Games saved before there were versions have no header
+    let first_version = bincode_options()
+      .serialize(&BinaryRecord {
+        winning_length: WINNING_LENGTH,
+        moves: area.moves().copied().collect(),
+        winner: None,
+      })
+      .unwrap();
+    let loaded = load(&first_version, Format::Bincode).unwrap();
+    assert_eq!(loaded.to_json(), area.to_json());
+
+    let mut future_version = save(&area, Format::Bincode);
+    future_version[BINARY_MAGIC.len()] = 99;
+    assert!(matches!(
+      load(&future_version, Format::Bincode),
+      Err(LoadError::Version(UnsupportedVersionError {
+        version: 99,
+        ..
+      }))
+    ));
+    assert!(matches!(
+      load(br#"{ "version": 99 }"#, Format::Json),
+      Err(LoadError::Json(FromJsonError::UnsupportedVersion(
+        UnsupportedVersionError { version: 99, .. }
+      )))
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_json_refuses_invalid_utf8() {
+    assert!(matches!(
+      load(b"{ \"version\": \xff }", Format::Json),
+      Err(LoadError::Utf8(_))
+    ));
+  }
+
+  #[test]
+  fn test_bincode_refuses_garbage() {
+    assert!(matches!(
+      load(&[1, 2, 3], Format::Bincode),
+      Err(LoadError::Bincode(_))
+    ));
+  }
+
+  #[test]
+  fn test_format_from_path() {
+    assert_eq!(Format::from_path(Path::new("game.json")), Format::Json);
+    assert_eq!(Format::from_path(Path::new("game.ftf")), Format::Bincode);
+    assert_eq!(Format::from_path(Path::new("game")), Format::Bincode);
+  }
+}
@@ -0,0 +1,326 @@
+//! Named player profiles, so that people sharing a computer keep their own
+//! preferences and achievements. Their ratings are kept in the game
+//! database of the `database` feature.
+//!
+//! The profiles are stored in a TOML file, by default in the configuration
+//! directory of the user:
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[profile]]
+//! name = "Aino"
+//! preferred_symbol = "Cross"
+//!
+//! [profile.settings]
+//! play_size = 60.0
+//! announce = true
+//! ```
+//!
+//! The file has the [`PROFILES_FORMAT_VERSION`] it was written in, and files
+//! from newer versions of the game are refused.
+use crate::achievements::AchievementProgress;
+use crate::rules::{first_version, Player, UnsupportedVersionError, VersionRecord};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Preferences of a player which the game follows when they play
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+  /// How large a single play is drawn, in logical pixels
+  pub play_size: f64,
+  /// Whether plays, turns and results are read aloud and printed for screen
+  /// readers
+  pub announce: bool,
+}
+
+impl Default for ProfileSettings {
+  fn default() -> Self {
+    ProfileSettings {
+      play_size: 80.0,
+      announce: false,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+  pub name: String,
+  /// The symbol the player would rather play with, if they care
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub preferred_symbol: Option<Player>,
+  #[serde(default)]
+  pub settings: ProfileSettings,
+  #[serde(default)]
+  pub achievements: AchievementProgress,
+}
+
+impl Profile {
+  /// A new player with the default settings
+  pub fn new(name: &str) -> Profile {
+    Profile {
+      name: name.to_owned(),
+      preferred_symbol: None,
+      settings: ProfileSettings::default(),
+      achievements: AchievementProgress::default(),
+    }
+  }
+}
+
+/// Which profile plays which symbol in a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seating<'a> {
+  pub naught: &'a Profile,
+  pub cross: &'a Profile,
+}
+
+/// Error caused by reading, writing or changing the profiles
+#[derive(Debug)]
+pub enum ProfileError {
+  Io(io::Error),
+  /// The profiles file isn't valid TOML in the expected shape
+  Parse(toml::de::Error),
+  Serialize(toml::ser::Error),
+  /// There already is a profile with the given name
+  DuplicateName(String),
+  /// There is no profile with the given name
+  NotFound(String),
+  /// The profiles were saved by a newer version of the game
+  Version(UnsupportedVersionError),
+}
+impl std::error::Error for ProfileError {}
+impl fmt::Display for ProfileError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ProfileError::Io(error) => write!(f, "Couldn't access the profiles: {}", error),
+      ProfileError::Parse(error) => write!(f, "Invalid profiles file: {}", error),
+      ProfileError::Serialize(error) => write!(f, "Couldn't save the profiles: {}", error),
+      ProfileError::DuplicateName(name) => write!(f, "There already is a profile named {}", name),
+      ProfileError::NotFound(name) => write!(f, "There is no profile named {}", name),
+      ProfileError::Version(error) => write!(f, "Invalid profiles file: {}", error),
+    }
+  }
+}
+impl From<io::Error> for ProfileError {
+  fn from(error: io::Error) -> Self {
+    ProfileError::Io(error)
+  }
+}
+
+/// The version of the format the profiles are saved in. Version 1 had no
+/// version field.
+pub const PROFILES_FORMAT_VERSION: u32 = 1;
+
+/// Every profile on this computer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profiles {
+  #[serde(default = "first_version")]
+  version: u32,
+  #[serde(default, rename = "profile")]
+  profiles: Vec<Profile>,
+}
+
+impl Default for Profiles {
+  fn default() -> Self {
+    Profiles {
+      version: PROFILES_FORMAT_VERSION,
+      profiles: vec![],
+    }
+  }
+}
+
+/// The directory of the game in the configuration directory of the user
+pub(crate) fn config_directory() -> Option<PathBuf> {
+  let config_directory = if cfg!(windows) {
+    env::var_os("APPDATA").map(PathBuf::from)
+  } else {
+    env::var_os("XDG_CONFIG_HOME")
+      .map(PathBuf::from)
+      .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+  };
+  config_directory.map(|directory| directory.join("first-to-five"))
+}
+
+/// Where the profiles are stored unless told otherwise: the
+/// `FIRST_TO_FIVE_PROFILES` environment variable, or `profiles.toml` in the
+/// configuration directory of the user
+pub fn default_path() -> Option<PathBuf> {
+  if let Some(path) = env::var_os("FIRST_TO_FIVE_PROFILES") {
+    return Some(PathBuf::from(path));
+  }
+  config_directory().map(|directory| directory.join("profiles.toml"))
+}
+
+impl Profiles {
+  /// Reads the profiles from the given file. A missing file has no profiles.
+  pub fn load(path: &Path) -> Result<Profiles, ProfileError> {
+    let text = match fs::read_to_string(path) {
+      Ok(text) => text,
+      Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Profiles::default()),
+      Err(error) => return Err(ProfileError::Io(error)),
+    };
+    let VersionRecord { version } = toml::from_str(&text).map_err(ProfileError::Parse)?;
+    UnsupportedVersionError::check(version, PROFILES_FORMAT_VERSION)
+      .map_err(ProfileError::Version)?;
+    let mut profiles: Profiles = toml::from_str(&text).map_err(ProfileError::Parse)?;
+    // There is only one version so far, so there is nothing to migrate
+    profiles.version = PROFILES_FORMAT_VERSION;
+    Ok(profiles)
+  }
+
+  /// Writes the profiles to the given file, creating its directory if needed
+  pub fn save(&self, path: &Path) -> Result<(), ProfileError> {
+    let text = toml::to_string_pretty(self).map_err(ProfileError::Serialize)?;
+    if let Some(directory) = path.parent() {
+      fs::create_dir_all(directory)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Profile> {
+    self.profiles.iter()
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Profile> {
+    self.profiles.iter().find(|profile| profile.name == name)
+  }
+
+  pub fn get_mut(&mut self, name: &str) -> Option<&mut Profile> {
+    self
+      .profiles
+      .iter_mut()
+      .find(|profile| profile.name == name)
+  }
+
+  /// Adds a new profile. Names have to be unique.
+  pub fn add(&mut self, profile: Profile) -> Result<(), ProfileError> {
+    if self.get(&profile.name).is_some() {
+      return Err(ProfileError::DuplicateName(profile.name));
+    }
+    self.profiles.push(profile);
+    Ok(())
+  }
+
+  pub fn remove(&mut self, name: &str) -> Result<Profile, ProfileError> {
+    let index = self
+      .profiles
+      .iter()
+      .position(|profile| profile.name == name)
+      .ok_or_else(|| ProfileError::NotFound(name.to_owned()))?;
+    Ok(self.profiles.remove(index))
+  }
+
+  /// Decides who plays which symbol. The first player gets the symbol they
+  /// prefer, the second one gets theirs if it's still free, and crosses go
+  /// to the first player if neither cares.
+  pub fn seat(&self, first: &str, second: &str) -> Result<Seating<'_>, ProfileError> {
+    let find = |name: &str| {
+      self
+        .get(name)
+        .ok_or_else(|| ProfileError::NotFound(name.to_owned()))
+    };
+    let (first, second) = (find(first)?, find(second)?);
+    let first_symbol = match (first.preferred_symbol, second.preferred_symbol) {
+      (Some(symbol), _) => symbol,
+      (None, Some(symbol)) => !symbol,
+      (None, None) => Player::Cross,
+    };
+    Ok(match first_symbol {
+      Player::Naught => Seating {
+        naught: first,
+        cross: second,
+      },
+      Player::Cross => Seating {
+        naught: second,
+        cross: first,
+      },
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn profiles() -> Profiles {
+    let mut profiles = Profiles::default();
+    profiles.add(Profile::new("Aino")).unwrap();
+    profiles
+      .add(Profile {
+        preferred_symbol: Some(Player::Cross),
+        ..Profile::new("Eero")
+      })
+      .unwrap();
+    profiles
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let path = env::temp_dir().join(format!(
+      "first-to-five-profiles-{}.toml",
+      std::process::id()
+    ));
+    let mut saved = profiles();
+    saved.get_mut("Aino").unwrap().settings.play_size = 60.0;
+    saved.save(&path).unwrap();
+
+    let loaded = Profiles::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, saved);
+    assert_eq!(
+      Profiles::load(&path).unwrap(),
+      Profiles::default(),
+      "A missing file should have no profiles"
+    );
+  }
+
+  #[test]
+  fn test_missing_fields_get_defaults() {
+    let profiles: Profiles = toml::from_str("[[profile]]\nname = \"Aino\"\n").unwrap();
+    assert_eq!(profiles.get("Aino"), Some(&Profile::new("Aino")));
+  }
+
+  #[test]
+  fn test_refuses_newer_versions() {
+    let path = env::temp_dir().join(format!(
+      "first-to-five-profiles-future-{}.toml",
+      std::process::id()
+    ));
+    fs::write(&path, "version = 99\n").unwrap();
+    let loaded = Profiles::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(
+      loaded,
+      Err(ProfileError::Version(UnsupportedVersionError {
+        version: 99,
+        ..
+      }))
+    ));
+  }
+
+  #[test]
+  fn test_names_are_unique() {
+    assert!(matches!(
+      profiles().add(Profile::new("Aino")),
+      Err(ProfileError::DuplicateName(_))
+    ));
+  }
+
+  #[test]
+  fn test_seating_follows_preferences() {
+    let profiles = profiles();
+    let seating = profiles.seat("Aino", "Eero").unwrap();
+    assert_eq!(seating.cross.name, "Eero");
+    assert_eq!(seating.naught.name, "Aino");
+    assert!(matches!(
+      profiles.seat("Aino", "Ville"),
+      Err(ProfileError::NotFound(_))
+    ));
+  }
+}
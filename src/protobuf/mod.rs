@@ -0,0 +1,509 @@
+//! Protocol Buffers encoding of the game state and the network messages.
+//!
+//! The schema lives in `proto/first_to_five.proto`. The types below are
+//! written by hand to match it, so that building the crate doesn't need
+//! `protoc` to be installed. Keep the two in sync when changing either one.
+use crate::protocol;
+pub use crate::rules::CoordinateOutOfRangeError;
+use crate::rules::{
+  self, coordinate_to_i64, GameArea, ReplayError, UnsupportedVersionError, WINNING_LENGTH,
+};
+use prost::Message as _;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Player {
+  Unspecified = 0,
+  Naught = 1,
+  Cross = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Move {
+  #[prost(sint64, tag = "1")]
+  pub x: i64,
+  #[prost(sint64, tag = "2")]
+  pub y: i64,
+  #[prost(enumeration = "Player", tag = "3")]
+  pub player: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Rules {
+  #[prost(int32, tag = "1")]
+  pub winning_length: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GameState {
+  #[prost(message, optional, tag = "1")]
+  pub rules: Option<Rules>,
+  #[prost(message, repeated, tag = "2")]
+  pub moves: Vec<Move>,
+  #[prost(enumeration = "Player", tag = "3")]
+  pub winner: i32,
+  #[prost(uint32, tag = "4")]
+  pub version: u32,
+}
+
+/// The version of the encoding of [`GameState`]. Games encoded before it had
+/// a version are read as version 1.
+pub const GAME_STATE_FORMAT_VERSION: u32 = 2;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Hello {
+  #[prost(uint32, tag = "1")]
+  pub version: u32,
+  #[prost(uint32, tag = "2")]
+  pub min_version: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HelloReply {
+  #[prost(oneof = "hello_reply::Reply", tags = "1, 2")]
+  pub reply: Option<hello_reply::Reply>,
+}
+
+pub mod hello_reply {
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct Incompatible {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    #[prost(uint32, tag = "2")]
+    pub min_version: u32,
+  }
+
+  #[derive(Clone, PartialEq, prost::Oneof)]
+  pub enum Reply {
+    #[prost(uint32, tag = "1")]
+    WelcomeVersion(u32),
+    #[prost(message, tag = "2")]
+    Incompatible(Incompatible),
+  }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Message {
+  #[prost(oneof = "message::Kind", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
+  pub kind: Option<message::Kind>,
+}
+
+pub mod message {
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct Join {
+    #[prost(string, tag = "1")]
+    pub name: String,
+  }
+
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct UndoRequest {}
+
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct DrawOffer {}
+
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct Resign {
+    #[prost(enumeration = "super::Player", tag = "1")]
+    pub player: i32,
+  }
+
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct ClockSync {
+    #[prost(uint64, tag = "1")]
+    pub naught_ms: u64,
+    #[prost(uint64, tag = "2")]
+    pub cross_ms: u64,
+  }
+
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct Chat {
+    #[prost(string, tag = "1")]
+    pub text: String,
+  }
+
+  #[derive(Clone, PartialEq, prost::Message)]
+  pub struct TimeControl {
+    #[prost(uint64, tag = "1")]
+    pub initial_ms: u64,
+    #[prost(uint64, tag = "2")]
+    pub increment_ms: u64,
+  }
+
+  #[derive(Clone, PartialEq, prost::Oneof)]
+  pub enum Kind {
+    #[prost(message, tag = "1")]
+    Join(Join),
+    #[prost(message, tag = "2")]
+    Move(super::Move),
+    #[prost(message, tag = "3")]
+    UndoRequest(UndoRequest),
+    #[prost(message, tag = "4")]
+    DrawOffer(DrawOffer),
+    #[prost(message, tag = "5")]
+    Resign(Resign),
+    #[prost(message, tag = "6")]
+    ClockSync(ClockSync),
+    #[prost(message, tag = "7")]
+    Chat(Chat),
+    #[prost(message, tag = "8")]
+    TimeControl(TimeControl),
+  }
+}
+
+/// Error caused by bytes which don't decode to a valid game or message
+#[derive(Debug)]
+pub enum DecodeError {
+  /// The bytes weren't a protobuf message of the expected type
+  Protobuf(prost::DecodeError),
+  /// A player field had a value which isn't in the schema
+  InvalidPlayer(i32),
+  /// A field which the message can't do without wasn't set
+  MissingField(&'static str),
+  /// The game breaks the rules
+  Replay(ReplayError),
+  /// The game was encoded by a newer version of the game
+  Version(UnsupportedVersionError),
+}
+impl std::error::Error for DecodeError {}
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      DecodeError::Protobuf(error) => write!(f, "Invalid protobuf: {}", error),
+      DecodeError::InvalidPlayer(value) => write!(f, "Invalid player {}", value),
+      DecodeError::MissingField(field) => write!(f, "Missing field {}", field),
+      DecodeError::Replay(error) => write!(f, "{}", error),
+      DecodeError::Version(error) => write!(f, "{}", error),
+    }
+  }
+}
+impl From<prost::DecodeError> for DecodeError {
+  fn from(error: prost::DecodeError) -> Self {
+    DecodeError::Protobuf(error)
+  }
+}
+
+impl From<Option<rules::Player>> for Player {
+  fn from(player: Option<rules::Player>) -> Self {
+    match player {
+      None => Player::Unspecified,
+      Some(rules::Player::Naught) => Player::Naught,
+      Some(rules::Player::Cross) => Player::Cross,
+    }
+  }
+}
+
+fn encode_player(player: Option<rules::Player>) -> i32 {
+  Player::from(player) as i32
+}
+
+fn decode_player(value: i32) -> Result<Option<rules::Player>, DecodeError> {
+  match Player::try_from(value) {
+    Ok(Player::Unspecified) => Ok(None),
+    Ok(Player::Naught) => Ok(Some(rules::Player::Naught)),
+    Ok(Player::Cross) => Ok(Some(rules::Player::Cross)),
+    Err(_) => Err(DecodeError::InvalidPlayer(value)),
+  }
+}
+
+fn decode_some_player(value: i32) -> Result<rules::Player, DecodeError> {
+  decode_player(value)?.ok_or(DecodeError::InvalidPlayer(value))
+}
+
+fn encode_move(player: rules::Player, x: i128, y: i128) -> Result<Move, CoordinateOutOfRangeError> {
+  Ok(Move {
+    x: coordinate_to_i64(x)?,
+    y: coordinate_to_i64(y)?,
+    player: encode_player(Some(player)),
+  })
+}
+
+/// Encodes the whole game, like [`GameArea::to_json`] does for JSON
+pub fn encode_game(area: &GameArea) -> Result<Vec<u8>, CoordinateOutOfRangeError> {
+  let state = GameState {
+    rules: Some(Rules {
+      winning_length: WINNING_LENGTH,
+    }),
+    moves: area
+      .moves()
+      .map(|play| encode_move(play.player(), play.x(), play.y()))
+      .collect::<Result<_, _>>()?,
+    winner: encode_player(area.winner()),
+    version: GAME_STATE_FORMAT_VERSION,
+  };
+  Ok(state.encode_to_vec())
+}
+
+/// Decodes a game encoded with [`encode_game`], replaying the moves so that a
+/// game which breaks the rules is refused.
+pub fn decode_game(bytes: &[u8]) -> Result<GameArea, DecodeError> {
+  let state = GameState::decode(bytes)?;
+  // Version 1 has the same shape, so there is nothing to migrate
+  UnsupportedVersionError::check(state.version.max(1), GAME_STATE_FORMAT_VERSION)
+    .map_err(DecodeError::Version)?;
+  let rules = state.rules.ok_or(DecodeError::MissingField("rules"))?;
+  let plays = state
+    .moves
+    .iter()
+    .map(|play| {
+      Ok((
+        decode_some_player(play.player)?,
+        play.x as i128,
+        play.y as i128,
+      ))
+    })
+    .collect::<Result<Vec<_>, DecodeError>>()?;
+  let winner = decode_player(state.winner)?;
+  GameArea::replay_to_result(rules.winning_length, plays, winner).map_err(DecodeError::Replay)
+}
+
+impl From<&protocol::Hello> for Hello {
+  fn from(hello: &protocol::Hello) -> Self {
+    Hello {
+      version: hello.version,
+      min_version: hello.min_version,
+    }
+  }
+}
+
+impl From<Hello> for protocol::Hello {
+  fn from(hello: Hello) -> Self {
+    protocol::Hello {
+      version: hello.version,
+      min_version: hello.min_version,
+    }
+  }
+}
+
+impl From<&protocol::HelloReply> for HelloReply {
+  fn from(reply: &protocol::HelloReply) -> Self {
+    let reply = match *reply {
+      protocol::HelloReply::Welcome { version } => hello_reply::Reply::WelcomeVersion(version),
+      protocol::HelloReply::Incompatible {
+        version,
+        min_version,
+      } => hello_reply::Reply::Incompatible(hello_reply::Incompatible {
+        version,
+        min_version,
+      }),
+    };
+    HelloReply { reply: Some(reply) }
+  }
+}
+
+impl TryFrom<HelloReply> for protocol::HelloReply {
+  type Error = DecodeError;
+
+  fn try_from(reply: HelloReply) -> Result<Self, Self::Error> {
+    match reply.reply {
+      Some(hello_reply::Reply::WelcomeVersion(version)) => {
+        Ok(protocol::HelloReply::Welcome { version })
+      }
+      Some(hello_reply::Reply::Incompatible(incompatible)) => {
+        Ok(protocol::HelloReply::Incompatible {
+          version: incompatible.version,
+          min_version: incompatible.min_version,
+        })
+      }
+      None => Err(DecodeError::MissingField("reply")),
+    }
+  }
+}
+
+/// Encodes a message of the wire protocol. [`protocol::Message::Unknown`]
+/// encodes to a message without any fields set.
+pub fn encode_message(message: &protocol::Message) -> Result<Vec<u8>, CoordinateOutOfRangeError> {
+  use message::Kind;
+  let kind = match message {
+    protocol::Message::Join { name } => Some(Kind::Join(message::Join { name: name.clone() })),
+    protocol::Message::Move { player, x, y } => Some(Kind::Move(encode_move(*player, *x, *y)?)),
+    protocol::Message::UndoRequest => Some(Kind::UndoRequest(message::UndoRequest {})),
+    protocol::Message::DrawOffer => Some(Kind::DrawOffer(message::DrawOffer {})),
+    protocol::Message::Resign { player } => Some(Kind::Resign(message::Resign {
+      player: encode_player(Some(*player)),
+    })),
+    protocol::Message::ClockSync {
+      naught_ms,
+      cross_ms,
+    } => Some(Kind::ClockSync(message::ClockSync {
+      naught_ms: *naught_ms,
+      cross_ms: *cross_ms,
+    })),
+    protocol::Message::Chat { text } => Some(Kind::Chat(message::Chat { text: text.clone() })),
+    protocol::Message::TimeControl { time_control } => {
+      let (initial_ms, increment_ms) = match *time_control {
+        rules::TimeControl::Clock {
+          initial_ms,
+          increment_ms,
+        } => (initial_ms, increment_ms),
+        rules::TimeControl::Correspondence => (0, 0),
+      };
+      Some(Kind::TimeControl(message::TimeControl {
+        initial_ms,
+        increment_ms,
+      }))
+    }
+    protocol::Message::Unknown => None,
+  };
+  Ok(Message { kind }.encode_to_vec())
+}
+
+/// Decodes a message encoded with [`encode_message`]. Messages of a kind this
+/// build doesn't know decode to [`protocol::Message::Unknown`].
+pub fn decode_message(bytes: &[u8]) -> Result<protocol::Message, DecodeError> {
+  use message::Kind;
+  let message = match Message::decode(bytes)?.kind {
+    Some(Kind::Join(join)) => protocol::Message::Join { name: join.name },
+    Some(Kind::Move(play)) => protocol::Message::Move {
+      player: decode_some_player(play.player)?,
+      x: play.x as i128,
+      y: play.y as i128,
+    },
+    Some(Kind::UndoRequest(_)) => protocol::Message::UndoRequest,
+    Some(Kind::DrawOffer(_)) => protocol::Message::DrawOffer,
+    Some(Kind::Resign(resign)) => protocol::Message::Resign {
+      player: decode_some_player(resign.player)?,
+    },
+    Some(Kind::ClockSync(clocks)) => protocol::Message::ClockSync {
+      naught_ms: clocks.naught_ms,
+      cross_ms: clocks.cross_ms,
+    },
+    Some(Kind::Chat(chat)) => protocol::Message::Chat { text: chat.text },
+    Some(Kind::TimeControl(time_control)) => protocol::Message::TimeControl {
+      time_control: match time_control {
+        message::TimeControl {
+          initial_ms: 0,
+          increment_ms: 0,
+        } => rules::TimeControl::Correspondence,
+        message::TimeControl {
+          initial_ms,
+          increment_ms,
+        } => rules::TimeControl::Clock {
+          initial_ms,
+          increment_ms,
+        },
+      },
+    },
+    None => protocol::Message::Unknown,
+  };
+  Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::IllegalPlayError;
+
+  #[test]
+  fn test_game_round_trip() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(rules::Player::Naught, x, x)?;
+      area.play(rules::Player::Cross, -x, 1)?;
+    }
+    area.play(rules::Player::Naught, 4, 4)?;
+
+    let decoded = decode_game(&encode_game(&area).unwrap()).unwrap();
+    assert_eq!(decoded.winner(), Some(rules::Player::Naught));
+    assert_eq!(decoded.to_json(), area.to_json());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_game_with_huge_coordinates() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    let x = i64::MAX as i128 + 1;
+    area.play(rules::Player::Naught, x, 0)?;
+    assert_eq!(encode_game(&area), Err(CoordinateOutOfRangeError(x)));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_decode_game_refuses_wrong_result() {
+    let state = GameState {
+      rules: Some(Rules {
+        winning_length: WINNING_LENGTH,
+      }),
+      moves: vec![],
+      winner: Player::Cross as i32,
+      version: GAME_STATE_FORMAT_VERSION,
+    };
+    assert!(matches!(
+      decode_game(&state.encode_to_vec()),
+      Err(DecodeError::Replay(ReplayError::ResultMismatch))
+    ));
+  }
+
+  #[test]
+  fn test_decode_game_versions() {
+    let state = GameState {
+      rules: Some(Rules {
+        winning_length: WINNING_LENGTH,
+      }),
+      moves: vec![],
+      winner: Player::Unspecified as i32,
+      version: 0,
+    };
+    assert!(decode_game(&state.encode_to_vec()).is_ok());
+
+    let state = GameState {
+      version: 99,
+      ..state
+    };
+    assert!(matches!(
+      decode_game(&state.encode_to_vec()),
+      Err(DecodeError::Version(UnsupportedVersionError {
+        version: 99,
+        ..
+      }))
+    ));
+  }
+
+  #[test]
+  fn test_message_round_trip() {
+    let messages = vec![
+      protocol::Message::Join {
+        name: "Vesa".to_string(),
+      },
+      protocol::Message::Move {
+        player: rules::Player::Cross,
+        x: -3,
+        y: 12,
+      },
+      protocol::Message::UndoRequest,
+      protocol::Message::DrawOffer,
+      protocol::Message::Resign {
+        player: rules::Player::Naught,
+      },
+      protocol::Message::ClockSync {
+        naught_ms: 1500,
+        cross_ms: 90_000,
+      },
+      protocol::Message::Chat {
+        text: "gg".to_string(),
+      },
+      protocol::Message::TimeControl {
+        time_control: rules::TimeControl::minutes(3, 2),
+      },
+      protocol::Message::TimeControl {
+        time_control: rules::TimeControl::Correspondence,
+      },
+      protocol::Message::Unknown,
+    ];
+    for message in messages {
+      let bytes = encode_message(&message).unwrap();
+      assert_eq!(decode_message(&bytes).unwrap(), message);
+    }
+  }
+
+  #[test]
+  fn test_hello_reply_round_trip() {
+    let reply = protocol::HelloReply::to(&protocol::Hello::default());
+    let bytes = HelloReply::from(&reply).encode_to_vec();
+    let decoded = HelloReply::decode(bytes.as_slice()).unwrap();
+    assert_eq!(protocol::HelloReply::try_from(decoded).unwrap(), reply);
+  }
+}
@@ -0,0 +1,313 @@
+use crate::rules::{Player, TimeControl};
+use alloc::string::String;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// The version of the wire protocol this build speaks.
+///
+/// Bump this whenever a message is added, removed or changes shape in a way an
+/// older peer couldn't understand.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// The oldest protocol version this build is still able to talk with
+pub const MIN_SUPPORTED_VERSION: u32 = 1;
+
+/// The first protocol version with [`Message::TimeControl`]
+pub const TIME_CONTROL_VERSION: u32 = 2;
+
+/// The first message a client sends after connecting.
+///
+/// Both ends advertise the range of versions they can speak so that the
+/// server can pick the newest version both understand, or refuse the
+/// connection with an explanation if the ranges don't overlap.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hello {
+  pub version: u32,
+  pub min_version: u32,
+}
+
+impl Default for Hello {
+  fn default() -> Self {
+    Hello {
+      version: PROTOCOL_VERSION,
+      min_version: MIN_SUPPORTED_VERSION,
+    }
+  }
+}
+
+/// The server's answer to a [`Hello`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HelloReply {
+  /// Both ends agreed to speak the given version from now on
+  Welcome { version: u32 },
+  /// The server can't speak any version the client can, so it will hang up
+  Incompatible { version: u32, min_version: u32 },
+}
+
+/// Messages exchanged once the handshake has completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+  /// A player wants to take a seat in the game
+  Join { name: String },
+  /// A player marks a new play on the game area
+  Move {
+    player: Player,
+    #[serde(with = "coordinate")]
+    x: i128,
+    #[serde(with = "coordinate")]
+    y: i128,
+  },
+  /// A player asks the opponent to take back the last play
+  UndoRequest,
+  /// A player offers to end the game without a winner
+  DrawOffer,
+  /// A player gives up
+  Resign { player: Player },
+  /// The remaining thinking time of both players, in milliseconds
+  ClockSync { naught_ms: u64, cross_ms: u64 },
+  /// The host tells the time control of the game before the first play.
+  /// Peers speaking version 1 don't know this message, so it is only sent
+  /// with [`time_control_for`] agreeing to a clock.
+  TimeControl { time_control: TimeControl },
+  /// A line of chat from a player
+  Chat { text: String },
+  /// A message type this build doesn't know about. Newer peers may send
+  /// these, and they should be ignored instead of dropping the connection.
+  #[serde(other)]
+  Unknown,
+}
+
+/// Coordinates as numbers when they fit in 64 bits, and as strings when they
+/// don't. Messages are tagged with a field of their own, so serde reads their
+/// fields into a buffer before the message, and the buffer can't hold
+/// integers any larger than 64 bits.
+mod coordinate {
+  use core::convert::TryFrom;
+  use core::fmt;
+  use serde::de::{self, Deserializer, Unexpected, Visitor};
+  use serde::Serializer;
+
+  pub fn serialize<S: Serializer>(coordinate: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    match i64::try_from(*coordinate) {
+      Ok(coordinate) => serializer.serialize_i64(coordinate),
+      Err(_) => serializer.collect_str(coordinate),
+    }
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+    deserializer.deserialize_any(CoordinateVisitor)
+  }
+
+  struct CoordinateVisitor;
+
+  impl<'de> Visitor<'de> for CoordinateVisitor {
+    type Value = i128;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "an integer, or a string with an integer")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<i128, E> {
+      Ok(value.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<i128, E> {
+      Ok(value.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<i128, E> {
+      Ok(value)
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<i128, E> {
+      i128::try_from(value).map_err(|_| E::invalid_value(Unexpected::Other("u128"), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<i128, E> {
+      value
+        .parse()
+        .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+    }
+  }
+}
+
+/// Error returned when the two ends of a connection don't share a protocol
+/// version they could both speak
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncompatibleVersionError {
+  pub local: (u32, u32),
+  pub remote: (u32, u32),
+}
+impl core::error::Error for IncompatibleVersionError {}
+impl fmt::Display for IncompatibleVersionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let (local_min, local) = self.local;
+    let (remote_min, remote) = self.remote;
+    write!(
+      f,
+      "Incompatible protocol versions: we speak {}..={} but the other end speaks {}..={}",
+      local_min, local, remote_min, remote
+    )?;
+    if remote < local_min {
+      write!(f, ", the other end needs to be updated")
+    } else {
+      write!(f, ", this game needs to be updated")
+    }
+  }
+}
+
+/// Picks the protocol version to use with a peer which sent us the given
+/// [`Hello`]. This is the newest version both ends can speak.
+pub fn negotiate(hello: &Hello) -> Result<u32, IncompatibleVersionError> {
+  let version = hello.version.min(PROTOCOL_VERSION);
+  if version < hello.min_version || version < MIN_SUPPORTED_VERSION {
+    return Err(IncompatibleVersionError {
+      local: (MIN_SUPPORTED_VERSION, PROTOCOL_VERSION),
+      remote: (hello.min_version, hello.version),
+    });
+  }
+  Ok(version)
+}
+
+/// The time control a host plays with a peer speaking the negotiated
+/// `version`. Peers older than [`TIME_CONTROL_VERSION`] would drop the
+/// [`Message::TimeControl`] as unknown and run no clock, so the host plays
+/// without one too, instead of the two ends running different clocks.
+pub fn time_control_for(version: u32, time_control: TimeControl) -> TimeControl {
+  if version < TIME_CONTROL_VERSION {
+    TimeControl::Correspondence
+  } else {
+    time_control
+  }
+}
+
+impl HelloReply {
+  /// Builds the server's reply to a client's [`Hello`]
+  pub fn to(hello: &Hello) -> HelloReply {
+    match negotiate(hello) {
+      Ok(version) => HelloReply::Welcome { version },
+      Err(_) => HelloReply::Incompatible {
+        version: PROTOCOL_VERSION,
+        min_version: MIN_SUPPORTED_VERSION,
+      },
+    }
+  }
+
+  /// Interprets the server's reply on the client side, resulting in the
+  /// version to speak from now on
+  pub fn accept(&self) -> Result<u32, IncompatibleVersionError> {
+    match *self {
+      HelloReply::Welcome { version }
+        if (MIN_SUPPORTED_VERSION..=PROTOCOL_VERSION).contains(&version) =>
+      {
+        Ok(version)
+      }
+      HelloReply::Welcome { version } => Err(IncompatibleVersionError {
+        local: (MIN_SUPPORTED_VERSION, PROTOCOL_VERSION),
+        remote: (version, version),
+      }),
+      HelloReply::Incompatible {
+        version,
+        min_version,
+      } => Err(IncompatibleVersionError {
+        local: (MIN_SUPPORTED_VERSION, PROTOCOL_VERSION),
+        remote: (min_version, version),
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_negotiate_same_version() {
+    assert_eq!(negotiate(&Hello::default()), Ok(PROTOCOL_VERSION));
+    assert_eq!(
+      HelloReply::to(&Hello::default()).accept(),
+      Ok(PROTOCOL_VERSION)
+    );
+  }
+
+  #[test]
+  fn test_negotiate_newer_client() {
+    let hello = Hello {
+      version: PROTOCOL_VERSION + 3,
+      min_version: MIN_SUPPORTED_VERSION,
+    };
+    assert_eq!(negotiate(&hello), Ok(PROTOCOL_VERSION));
+  }
+
+  #[test]
+  fn test_negotiate_too_new_client() {
+    let hello = Hello {
+      version: PROTOCOL_VERSION + 3,
+      min_version: PROTOCOL_VERSION + 2,
+    };
+    let error = negotiate(&hello).unwrap_err();
+    assert_eq!(error.remote, (PROTOCOL_VERSION + 2, PROTOCOL_VERSION + 3));
+    assert!(error.to_string().ends_with("this game needs to be updated"));
+    assert_eq!(
+      HelloReply::to(&hello),
+      HelloReply::Incompatible {
+        version: PROTOCOL_VERSION,
+        min_version: MIN_SUPPORTED_VERSION
+      }
+    );
+  }
+
+  #[test]
+  fn test_negotiate_too_old_client() {
+    let hello = Hello {
+      version: MIN_SUPPORTED_VERSION - 1,
+      min_version: 0,
+    };
+    let error = negotiate(&hello).unwrap_err();
+    assert!(error
+      .to_string()
+      .ends_with("the other end needs to be updated"));
+  }
+
+  #[test]
+  fn test_time_control_for_old_peers() {
+    let blitz = TimeControl::minutes(3, 2);
+    assert_eq!(
+      time_control_for(MIN_SUPPORTED_VERSION, blitz),
+      TimeControl::Correspondence
+    );
+    assert_eq!(time_control_for(PROTOCOL_VERSION, blitz), blitz);
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn test_move_round_trip() {
+    let near = Message::Move {
+      player: Player::Cross,
+      x: 1,
+      y: -2,
+    };
+    let json = serde_json::to_string(&near).unwrap();
+    assert!(json.contains(r#""x":1,"y":-2"#), "{}", json);
+
+    for &x in &[0, i64::MAX as i128 + 1, i128::MAX, i128::MIN] {
+      let message = Message::Move {
+        player: Player::Naught,
+        x,
+        y: -7,
+      };
+      let json = serde_json::to_string(&message).unwrap();
+      assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), message);
+    }
+  }
+
+  #[test]
+  fn test_accept_welcome_for_unknown_version() {
+    let reply = HelloReply::Welcome {
+      version: PROTOCOL_VERSION + 1,
+    };
+    assert!(reply.accept().is_err());
+  }
+}
@@ -0,0 +1,74 @@
+//! Elo ratings of players.
+//!
+//! Everybody starts from [`INITIAL_RATING`]. After a rated game both players'
+//! ratings move towards the result: beating a higher rated opponent gains
+//! more than beating a lower rated one. A game which ends without a winner
+//! counts as a draw.
+//!
+//! Computer opponents are rated like anybody else, under a name of their own
+//! for each difficulty level.
+use crate::rules::Player;
+
+/// The rating of a player who hasn't played any rated games
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// How much a single game can move a rating at most
+pub const K_FACTOR: f64 = 32.0;
+
+/// The score a player with the given rating is expected to get against the
+/// opponent, between 0 for a sure loss and 1 for a sure win
+pub fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+  1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+/// The new rating of a player after a game where they scored `score`: 1 for
+/// a win, 0.5 for a draw and 0 for a loss
+pub fn updated_rating(rating: f64, opponent_rating: f64, score: f64) -> f64 {
+  rating + K_FACTOR * (score - expected_score(rating, opponent_rating))
+}
+
+/// The new ratings of the naught and the cross player after a game with the
+/// given winner
+pub fn rate_game(naught_rating: f64, cross_rating: f64, winner: Option<Player>) -> (f64, f64) {
+  let naught_score = match winner {
+    Some(Player::Naught) => 1.0,
+    Some(Player::Cross) => 0.0,
+    None => 0.5,
+  };
+  (
+    updated_rating(naught_rating, cross_rating, naught_score),
+    updated_rating(cross_rating, naught_rating, 1.0 - naught_score),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: f64, expected: f64) {
+    assert!(
+      (actual - expected).abs() < 0.01,
+      "Expected {} but got {}",
+      expected,
+      actual
+    );
+  }
+
+  #[test]
+  fn test_equal_ratings() {
+    assert_close(expected_score(1500.0, 1500.0), 0.5);
+    let (naught, cross) = rate_game(1500.0, 1500.0, Some(Player::Cross));
+    assert_close(naught, 1484.0);
+    assert_close(cross, 1516.0);
+    assert_eq!(rate_game(1500.0, 1500.0, None), (1500.0, 1500.0));
+  }
+
+  #[test]
+  fn test_upset_gains_more() {
+    // A 400 point difference makes the favourite ten times as likely to win
+    assert_close(expected_score(1900.0, 1500.0), 10.0 / 11.0);
+    let (underdog, favourite) = rate_game(1500.0, 1900.0, Some(Player::Naught));
+    assert_close(underdog, 1500.0 + 32.0 * 10.0 / 11.0);
+    assert_close(favourite, 1900.0 - 32.0 * 10.0 / 11.0);
+  }
+}
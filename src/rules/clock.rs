@@ -0,0 +1,231 @@
+//! Chess clocks for timed games.
+//!
+//! The rules don't know what time it is, so whoever runs the game tells the
+//! [`Clocks`] how long each player thought about their play.
+use super::Player;
+use core::fmt;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// How much thinking time the players get
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimeControl {
+  /// Both players start with `initial_ms` of thinking time and gain
+  /// `increment_ms` after each of their plays
+  Clock { initial_ms: u64, increment_ms: u64 },
+  /// No clock, as when playing by mail
+  Correspondence,
+}
+
+/// The time controls offered to players: 1+0, 3+2, 10+5 and correspondence
+pub const PRESETS: [TimeControl; 4] = [
+  TimeControl::minutes(1, 0),
+  TimeControl::minutes(3, 2),
+  TimeControl::minutes(10, 5),
+  TimeControl::Correspondence,
+];
+
+impl TimeControl {
+  /// A clock with the given minutes of thinking time and seconds of
+  /// increment, written as `minutes+increment`. Panics if the times don't
+  /// fit in milliseconds, see [`TimeControl::checked_minutes`].
+  pub const fn minutes(minutes: u64, increment_seconds: u64) -> TimeControl {
+    match TimeControl::checked_minutes(minutes, increment_seconds) {
+      Some(time_control) => time_control,
+      None => panic!("The time control is too long"),
+    }
+  }
+
+  /// Like [`TimeControl::minutes`], or `None` if the times don't fit in
+  /// milliseconds
+  pub const fn checked_minutes(minutes: u64, increment_seconds: u64) -> Option<TimeControl> {
+    match (
+      minutes.checked_mul(60_000),
+      increment_seconds.checked_mul(1000),
+    ) {
+      (Some(initial_ms), Some(increment_ms)) => Some(TimeControl::Clock {
+        initial_ms,
+        increment_ms,
+      }),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for TimeControl {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TimeControl::Clock {
+        initial_ms,
+        increment_ms,
+      } if initial_ms % 60_000 == 0 && increment_ms % 1000 == 0 => {
+        write!(f, "{}+{}", initial_ms / 60_000, increment_ms / 1000)
+      }
+      TimeControl::Clock {
+        initial_ms,
+        increment_ms,
+      } => write!(f, "{}ms+{}ms", initial_ms, increment_ms),
+      TimeControl::Correspondence => write!(f, "correspondence"),
+    }
+  }
+}
+
+/// Error caused by a time control which isn't `correspondence` or of the
+/// form `minutes+increment`, like `3+2`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseTimeControlError;
+impl core::error::Error for ParseTimeControlError {}
+impl fmt::Display for ParseTimeControlError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Invalid time control, expected minutes and increment seconds like 3+2, or correspondence"
+    )
+  }
+}
+
+impl FromStr for TimeControl {
+  type Err = ParseTimeControlError;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    if text == "correspondence" {
+      return Ok(TimeControl::Correspondence);
+    }
+    let (minutes, increment) = text.split_once('+').ok_or(ParseTimeControlError)?;
+    let minutes = minutes.parse().map_err(|_| ParseTimeControlError)?;
+    let increment = increment.parse().map_err(|_| ParseTimeControlError)?;
+    if minutes == 0 {
+      return Err(ParseTimeControlError);
+    }
+    TimeControl::checked_minutes(minutes, increment).ok_or(ParseTimeControlError)
+  }
+}
+
+/// The player ran out of thinking time before making their play
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfTimeError(pub Player);
+impl core::error::Error for OutOfTimeError {}
+impl fmt::Display for OutOfTimeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?} ran out of time", self.0)
+  }
+}
+
+/// The remaining thinking time of both players in a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clocks {
+  time_control: TimeControl,
+  naught_ms: u64,
+  cross_ms: u64,
+}
+
+impl Clocks {
+  pub fn new(time_control: TimeControl) -> Clocks {
+    let initial_ms = match time_control {
+      TimeControl::Clock { initial_ms, .. } => initial_ms,
+      TimeControl::Correspondence => 0,
+    };
+    Clocks {
+      time_control,
+      naught_ms: initial_ms,
+      cross_ms: initial_ms,
+    }
+  }
+
+  pub fn time_control(&self) -> TimeControl {
+    self.time_control
+  }
+
+  /// The thinking time the player has left, or `None` without a clock
+  pub fn remaining_ms(&self, player: Player) -> Option<u64> {
+    match self.time_control {
+      TimeControl::Clock { .. } => Some(match player {
+        Player::Naught => self.naught_ms,
+        Player::Cross => self.cross_ms,
+      }),
+      TimeControl::Correspondence => None,
+    }
+  }
+
+  /// Whether the player has run out of time after thinking for `elapsed_ms`
+  /// on their current play
+  pub fn is_out_of_time(&self, player: Player, elapsed_ms: u64) -> bool {
+    self
+      .remaining_ms(player)
+      .is_some_and(|remaining_ms| elapsed_ms > remaining_ms)
+  }
+
+  /// Charges the player for thinking `elapsed_ms` about the play they just
+  /// made, and gives them the increment
+  pub fn record_play(&mut self, player: Player, elapsed_ms: u64) -> Result<(), OutOfTimeError> {
+    let increment_ms = match self.time_control {
+      TimeControl::Clock { increment_ms, .. } => increment_ms,
+      TimeControl::Correspondence => return Ok(()),
+    };
+    if self.is_out_of_time(player, elapsed_ms) {
+      return Err(OutOfTimeError(player));
+    }
+    let remaining_ms = match player {
+      Player::Naught => &mut self.naught_ms,
+      Player::Cross => &mut self.cross_ms,
+    };
+    *remaining_ms = (*remaining_ms - elapsed_ms).saturating_add(increment_ms);
+    Ok(())
+  }
+
+  /// Sets the remaining times from the other end of a network game, see
+  /// [`crate::protocol::Message::ClockSync`]
+  pub fn sync(&mut self, naught_ms: u64, cross_ms: u64) {
+    self.naught_ms = naught_ms;
+    self.cross_ms = cross_ms;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::string::ToString;
+
+  #[test]
+  fn test_parse_presets() {
+    for preset in &PRESETS {
+      assert_eq!(preset.to_string().parse(), Ok(*preset));
+    }
+    assert_eq!(
+      "3+2".parse(),
+      Ok(TimeControl::Clock {
+        initial_ms: 180_000,
+        increment_ms: 2000
+      })
+    );
+    assert_eq!("3".parse::<TimeControl>(), Err(ParseTimeControlError));
+    assert_eq!("0+5".parse::<TimeControl>(), Err(ParseTimeControlError));
+    assert_eq!(
+      "400000000000000+0".parse::<TimeControl>(),
+      Err(ParseTimeControlError)
+    );
+    assert_eq!(
+      "1+20000000000000000".parse::<TimeControl>(),
+      Err(ParseTimeControlError)
+    );
+  }
+
+  #[test]
+  fn test_clocks() {
+    let mut clocks = Clocks::new(TimeControl::minutes(1, 2));
+    clocks.record_play(Player::Cross, 10_000).unwrap();
+    assert_eq!(clocks.remaining_ms(Player::Cross), Some(52_000));
+    assert_eq!(clocks.remaining_ms(Player::Naught), Some(60_000));
+
+    assert!(clocks.is_out_of_time(Player::Naught, 60_001));
+    assert_eq!(
+      clocks.record_play(Player::Naught, 60_001),
+      Err(OutOfTimeError(Player::Naught))
+    );
+
+    let mut clocks = Clocks::new(TimeControl::Correspondence);
+    assert_eq!(clocks.record_play(Player::Cross, u64::MAX), Ok(()));
+    assert_eq!(clocks.remaining_ms(Player::Cross), None);
+  }
+}
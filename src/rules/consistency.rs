@@ -0,0 +1,273 @@
+//! Checks for the internal invariants of a [`GameArea`].
+//!
+//! The rules keep the plays in more than one shape at once: the board, the
+//! history of plays, the bounding box and the winner. These have to agree
+//! with each other, and [`GameArea::check_consistency`] tells if they don't.
+use super::{GameArea, Play, Player, WINNING_LENGTH};
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The edges of a game area. The right and bottom edges are exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+  pub left: i128,
+  pub top: i128,
+  pub right: i128,
+  pub bottom: i128,
+}
+
+/// An invariant of a [`GameArea`] which doesn't hold
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsistencyViolation {
+  /// The bounding box isn't the smallest one containing every play
+  BoundingBox { expected: Bounds, actual: Bounds },
+  /// The history has a spot which isn't marked on the board
+  MissingPlay { index: usize, x: i128, y: i128 },
+  /// The history has the same spot more than once
+  DuplicateHistory { index: usize, x: i128, y: i128 },
+  /// A spot is marked on the board but isn't in the history
+  UnrecordedPlay { x: i128, y: i128 },
+  /// The winner is set but the last play didn't complete a line for them
+  WinnerWithoutLine(Player),
+  /// A player has a winning line but the winner isn't set to them
+  UnrecordedWinner { player: Player, x: i128, y: i128 },
+}
+impl core::error::Error for ConsistencyViolation {}
+impl fmt::Display for ConsistencyViolation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ConsistencyViolation::BoundingBox { expected, actual } => write!(
+        f,
+        "Bounding box is {:?} but the plays span {:?}",
+        actual, expected
+      ),
+      ConsistencyViolation::MissingPlay { index, x, y } => write!(
+        f,
+        "Play number {} at ({}, {}) isn't marked on the board",
+        index + 1,
+        x,
+        y
+      ),
+      ConsistencyViolation::DuplicateHistory { index, x, y } => write!(
+        f,
+        "Play number {} at ({}, {}) was already played before",
+        index + 1,
+        x,
+        y
+      ),
+      ConsistencyViolation::UnrecordedPlay { x, y } => {
+        write!(f, "Spot ({}, {}) is marked but not in the history", x, y)
+      }
+      ConsistencyViolation::WinnerWithoutLine(player) => write!(
+        f,
+        "{:?} is the winner but the last play didn't complete their line",
+        player
+      ),
+      ConsistencyViolation::UnrecordedWinner { player, x, y } => write!(
+        f,
+        "{:?} has a winning line through ({}, {}) but isn't the winner",
+        player, x, y
+      ),
+    }
+  }
+}
+
+fn is_winning_line(line: Option<Vec<&Play>>) -> bool {
+  line.is_some_and(|line| line.len() as i128 >= WINNING_LENGTH as i128)
+}
+
+impl GameArea {
+  /// Verifies that the board, the history of plays, the bounding box and the
+  /// winner all agree with each other, listing every disagreement found.
+  ///
+  /// This goes through every play, so it is meant for tests and debugging
+  /// rather than for every frame.
+  pub fn check_consistency(&self) -> Result<(), Vec<ConsistencyViolation>> {
+    let mut violations = vec![];
+    let board: Vec<&Play> = self
+      .games
+      .0
+      .values()
+      .flat_map(|column| column.values())
+      .collect();
+
+    let actual = Bounds {
+      left: self.left,
+      top: self.top,
+      right: self.right,
+      bottom: self.bottom,
+    };
+    let expected = board
+      .iter()
+      .fold(None, |bounds: Option<Bounds>, play| {
+        Some(match bounds {
+          None => Bounds {
+            left: play.x,
+            top: play.y,
+            right: play.x + 1,
+            bottom: play.y + 1,
+          },
+          Some(bounds) => Bounds {
+            left: bounds.left.min(play.x),
+            top: bounds.top.min(play.y),
+            right: bounds.right.max(play.x + 1),
+            bottom: bounds.bottom.max(play.y + 1),
+          },
+        })
+      })
+      // An empty area is all zeroes until the first play
+      .unwrap_or(Bounds {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+      });
+    if actual != expected {
+      violations.push(ConsistencyViolation::BoundingBox { expected, actual });
+    }
+
+    let mut seen = BTreeSet::new();
+    for (index, &(x, y)) in self.history.iter().enumerate() {
+      if !seen.insert((x, y)) {
+        violations.push(ConsistencyViolation::DuplicateHistory { index, x, y });
+      } else if self.games.get(&(x, y)).is_none() {
+        violations.push(ConsistencyViolation::MissingPlay { index, x, y });
+      }
+    }
+    for play in &board {
+      if !seen.contains(&(play.x, play.y)) {
+        violations.push(ConsistencyViolation::UnrecordedPlay {
+          x: play.x,
+          y: play.y,
+        });
+      }
+    }
+
+    match self.winner {
+      Some(winner) => {
+        let last_play = self.history.last().and_then(|point| self.games.get(point));
+        let completed_line = last_play.is_some_and(|play| {
+          play.player == winner
+            && is_winning_line(self.games.longest_consecutive_line(&(play.x, play.y)))
+        });
+        if !completed_line {
+          violations.push(ConsistencyViolation::WinnerWithoutLine(winner));
+        }
+      }
+      None => {
+        for play in &board {
+          if is_winning_line(self.games.longest_consecutive_line(&(play.x, play.y))) {
+            violations.push(ConsistencyViolation::UnrecordedWinner {
+              player: play.player,
+              x: play.x,
+              y: play.y,
+            });
+            break;
+          }
+        }
+      }
+    }
+
+    if violations.is_empty() {
+      Ok(())
+    } else {
+      Err(violations)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::IllegalPlayError;
+
+  fn won_game() -> Result<GameArea, IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, -1)?;
+    }
+    area.play(Player::Cross, 4, 0)?;
+    Ok(area)
+  }
+
+  #[test]
+  fn test_played_games_are_consistent() -> Result<(), IllegalPlayError> {
+    assert_eq!(GameArea::default().check_consistency(), Ok(()));
+    assert_eq!(won_game()?.check_consistency(), Ok(()));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_broken_bounding_box() -> Result<(), IllegalPlayError> {
+    let mut area = won_game()?;
+    area.right += 1;
+    assert_eq!(
+      area.check_consistency(),
+      Err(vec![ConsistencyViolation::BoundingBox {
+        expected: Bounds {
+          left: 0,
+          top: -1,
+          right: 5,
+          bottom: 1
+        },
+        actual: Bounds {
+          left: 0,
+          top: -1,
+          right: 6,
+          bottom: 1
+        },
+      }])
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_history_not_matching_board() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    area.play(Player::Cross, 0, 0)?;
+    area.play(Player::Naught, 1, 0)?;
+    area.history.push((0, 0));
+    area.history.remove(1);
+    assert_eq!(
+      area.check_consistency(),
+      Err(vec![
+        ConsistencyViolation::DuplicateHistory {
+          index: 1,
+          x: 0,
+          y: 0
+        },
+        ConsistencyViolation::UnrecordedPlay { x: 1, y: 0 },
+      ])
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_winner_not_matching_lines() -> Result<(), IllegalPlayError> {
+    let mut area = won_game()?;
+    area.winner = Some(Player::Naught);
+    assert_eq!(
+      area.check_consistency(),
+      Err(vec![ConsistencyViolation::WinnerWithoutLine(
+        Player::Naught
+      )])
+    );
+
+    area.winner = None;
+    assert!(matches!(
+      area.check_consistency().unwrap_err().as_slice(),
+      [ConsistencyViolation::UnrecordedWinner {
+        player: Player::Cross,
+        ..
+      }]
+    ));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,293 @@
+//! The JSON interchange format for a whole game.
+//!
+//! The format is described by the JSON schema in `docs/game-state.schema.json`.
+//! A game that Cross won looks like this:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "rules": { "winning_length": 5 },
+//!   "moves": [
+//!     { "x": 0, "y": 0, "player": "Cross" },
+//!     { "x": 0, "y": 1, "player": "Naught" },
+//!     ...
+//!   ],
+//!   "result": { "status": "won", "winner": "Cross" }
+//! }
+//! ```
+//!
+//! Clocks are kept apart from the game area, so they aren't part of the format.
+//!
+//! Every export has the [`FORMAT_VERSION`] it was written in. Games exported
+//! in older versions are migrated to the current one when they are imported,
+//! and games from newer versions of the game are refused.
+use super::{
+  first_version, GameArea, Play, Player, ReplayError, UnsupportedVersionError, VersionRecord,
+  WINNING_LENGTH,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The version of the format games are exported in. Version 1 had no
+/// version field.
+pub const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GameRecord {
+  #[serde(default = "first_version")]
+  version: u32,
+  rules: Rules,
+  moves: Vec<Play>,
+  result: GameResult,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Rules {
+  winning_length: i32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum GameResult {
+  InProgress,
+  Won { winner: Player },
+}
+
+/// Error caused by trying to import a game from JSON which isn't valid
+#[derive(Debug)]
+pub enum FromJsonError {
+  /// The input wasn't JSON in the expected shape
+  Json(serde_json::Error),
+  /// The game was exported by a newer version of the game
+  UnsupportedVersion(UnsupportedVersionError),
+  /// The game breaks the rules
+  Replay(ReplayError),
+}
+impl std::error::Error for FromJsonError {}
+impl fmt::Display for FromJsonError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      FromJsonError::Json(error) => write!(f, "Invalid game JSON: {}", error),
+      FromJsonError::UnsupportedVersion(error) => write!(f, "{}", error),
+      FromJsonError::Replay(error) => write!(f, "{}", error),
+    }
+  }
+}
+impl From<serde_json::Error> for FromJsonError {
+  fn from(error: serde_json::Error) -> Self {
+    FromJsonError::Json(error)
+  }
+}
+impl From<UnsupportedVersionError> for FromJsonError {
+  fn from(error: UnsupportedVersionError) -> Self {
+    FromJsonError::UnsupportedVersion(error)
+  }
+}
+impl From<ReplayError> for FromJsonError {
+  fn from(error: ReplayError) -> Self {
+    FromJsonError::Replay(error)
+  }
+}
+
+/// Upgrades a game exported in an older version of the format to the
+/// current one
+fn migrate(mut record: GameRecord) -> GameRecord {
+  if record.version == 1 {
+    // Version 1 only lacked the version
+    record.version = 2;
+  }
+  record
+}
+
+impl GameArea {
+  /// Exports the whole game as JSON, see the module documentation for the
+  /// format.
+  pub fn to_json(&self) -> String {
+    let record = GameRecord {
+      version: FORMAT_VERSION,
+      rules: Rules {
+        winning_length: WINNING_LENGTH,
+      },
+      moves: self.moves().copied().collect(),
+      result: match self.winner {
+        None => GameResult::InProgress,
+        Some(winner) => GameResult::Won { winner },
+      },
+    };
+    // Serializing plain structs to a string can't fail
+    serde_json::to_string_pretty(&record).unwrap()
+  }
+
+  /// Imports a game exported with [`GameArea::to_json`].
+  ///
+  /// The moves are replayed one by one, so a game which breaks the rules
+  /// is refused instead of being imported as is.
+  pub fn from_json(json: &str) -> Result<GameArea, FromJsonError> {
+    let VersionRecord { version } = serde_json::from_str(json)?;
+    UnsupportedVersionError::check(version, FORMAT_VERSION)?;
+    let record: GameRecord = migrate(serde_json::from_str(json)?);
+    let winner = match record.result {
+      GameResult::InProgress => None,
+      GameResult::Won { winner } => Some(winner),
+    };
+    let plays = record
+      .moves
+      .iter()
+      .map(|play| (play.player, play.x, play.y));
+    Ok(GameArea::replay_to_result(
+      record.rules.winning_length,
+      plays,
+      winner,
+    )?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::IllegalPlayError;
+
+  fn import_error(json: &str) -> FromJsonError {
+    match GameArea::from_json(json) {
+      Ok(_) => panic!("Expected the import to fail"),
+      Err(error) => error,
+    }
+  }
+
+  #[test]
+  fn test_round_trip() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, -1)?;
+    }
+    area.play(Player::Cross, 4, 0)?;
+
+    let imported = GameArea::from_json(&area.to_json()).unwrap();
+    assert_eq!(imported.winner(), Some(Player::Cross));
+    assert_eq!(imported.to_string(), area.to_string());
+    assert_eq!(imported.to_json(), area.to_json());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_export_format() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    area.play(Player::Cross, 0, 0)?;
+    area.play(Player::Naught, -1, 2)?;
+
+    let json: serde_json::Value = serde_json::from_str(&area.to_json()).unwrap();
+    assert_eq!(
+      json,
+      serde_json::json!({
+        "version": 2,
+        "rules": { "winning_length": 5 },
+        "moves": [
+          { "x": 0, "y": 0, "player": "Cross" },
+          { "x": -1, "y": 2, "player": "Naught" },
+        ],
+        "result": { "status": "in_progress" },
+      })
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_import_refuses_illegal_moves() {
+    let error = import_error(
+      r#"{
+        "rules": { "winning_length": 5 },
+        "moves": [
+          { "x": 0, "y": 0, "player": "Cross" },
+          { "x": 0, "y": 0, "player": "Naught" }
+        ],
+        "result": { "status": "in_progress" }
+      }"#,
+    );
+    assert!(matches!(
+      error,
+      FromJsonError::Replay(ReplayError::IllegalMove {
+        index: 1,
+        error: IllegalPlayError::AlreadyMarked(Player::Cross)
+      })
+    ));
+  }
+
+  #[test]
+  fn test_import_refuses_coordinates_beyond_bounds() {
+    let json = format!(
+      r#"{{
+        "rules": {{ "winning_length": 5 }},
+        "moves": [{{ "x": 0, "y": {}, "player": "Cross" }}],
+        "result": {{ "status": "in_progress" }}
+      }}"#,
+      i128::MIN
+    );
+    assert!(matches!(
+      import_error(&json),
+      FromJsonError::Replay(ReplayError::IllegalMove {
+        index: 0,
+        error: IllegalPlayError::OutOfBounds
+      })
+    ));
+  }
+
+  #[test]
+  fn test_import_refuses_wrong_result() {
+    let error = import_error(
+      r#"{
+        "rules": { "winning_length": 5 },
+        "moves": [{ "x": 0, "y": 0, "player": "Cross" }],
+        "result": { "status": "won", "winner": "Cross" }
+      }"#,
+    );
+    assert!(matches!(
+      error,
+      FromJsonError::Replay(ReplayError::ResultMismatch)
+    ));
+  }
+
+  #[test]
+  fn test_import_refuses_other_rules() {
+    let error = import_error(
+      r#"{
+        "rules": { "winning_length": 3 },
+        "moves": [],
+        "result": { "status": "in_progress" }
+      }"#,
+    );
+    assert!(matches!(
+      error,
+      FromJsonError::Replay(ReplayError::UnsupportedRules { winning_length: 3 })
+    ));
+  }
+
+  #[test]
+  fn test_import_migrates_first_version() {
+    let area = GameArea::from_json(
+      r#"{
+        "rules": { "winning_length": 5 },
+        "moves": [{ "x": 0, "y": 0, "player": "Cross" }],
+        "result": { "status": "in_progress" }
+      }"#,
+    )
+    .unwrap();
+    assert_eq!(area.moves().count(), 1);
+  }
+
+  #[test]
+  fn test_import_refuses_newer_versions() {
+    let error = import_error(r#"{ "version": 3, "board": "in a format of the future" }"#);
+    assert!(matches!(
+      error,
+      FromJsonError::UnsupportedVersion(UnsupportedVersionError { version: 3, .. })
+    ));
+  }
+
+  #[test]
+  fn test_import_refuses_garbage() {
+    assert!(matches!(import_error("[1, 2, 3]"), FromJsonError::Json(_)));
+  }
+}
@@ -1,12 +1,33 @@
-use std::collections::BTreeMap;
-use std::fmt;
-
-#[derive(fmt::Debug, PartialEq, PartialOrd, Clone, Copy)]
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+mod clock;
+mod consistency;
+#[cfg(feature = "std")]
+mod json;
+mod notation;
+mod replay;
+mod version;
+pub use clock::{Clocks, OutOfTimeError, ParseTimeControlError, TimeControl, PRESETS};
+pub use consistency::{Bounds, ConsistencyViolation};
+#[cfg(feature = "std")]
+pub use json::{FromJsonError, FORMAT_VERSION};
+pub use notation::ParsePositionError;
+pub use replay::ReplayError;
+pub use version::UnsupportedVersionError;
+#[cfg(feature = "std")]
+pub(crate) use version::{first_version, VersionRecord};
+
+#[derive(fmt::Debug, PartialEq, PartialOrd, Clone, Copy, Serialize, Deserialize)]
 pub enum Player {
   Naught,
   Cross,
 }
-impl std::ops::Not for Player {
+impl core::ops::Not for Player {
   type Output = Self;
 
   fn not(self) -> Self::Output {
@@ -17,7 +38,42 @@ impl std::ops::Not for Player {
   }
 }
 
-#[derive(Default)]
+impl Player {
+  /// The name the player is stored with, the same as in JSON
+  pub fn name(self) -> &'static str {
+    match self {
+      Player::Naught => "Naught",
+      Player::Cross => "Cross",
+    }
+  }
+
+  /// The player with the given [`Player::name`]
+  pub fn from_name(name: &str) -> Option<Player> {
+    match name {
+      "Naught" => Some(Player::Naught),
+      "Cross" => Some(Player::Cross),
+      _ => None,
+    }
+  }
+}
+
+/// Error caused by a coordinate which doesn't fit the 64 bits that formats
+/// other than JSON store coordinates in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateOutOfRangeError(pub i128);
+impl core::error::Error for CoordinateOutOfRangeError {}
+impl fmt::Display for CoordinateOutOfRangeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Coordinate {} doesn't fit in a 64-bit integer", self.0)
+  }
+}
+
+/// The coordinate as a 64-bit integer, for the formats which store them so
+pub fn coordinate_to_i64(coordinate: i128) -> Result<i64, CoordinateOutOfRangeError> {
+  i64::try_from(coordinate).map_err(|_| CoordinateOutOfRangeError(coordinate))
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct GameArea {
   left: i128,
   top: i128,
@@ -25,41 +81,106 @@ pub struct GameArea {
   bottom: i128,
   winner: Option<Player>,
   games: PlayedGames,
+  /// Coordinates of every play in the order they were marked
+  history: Vec<(i128, i128)>,
 }
 
 /// Error caused by trying to mark a new play to a game which has already been
 /// completed
 #[derive(Debug, Clone)]
 pub struct WinnerAlreadySelectedError;
-impl std::error::Error for WinnerAlreadySelectedError {}
+impl core::error::Error for WinnerAlreadySelectedError {}
 impl fmt::Display for WinnerAlreadySelectedError {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     write!(f, "Winner already selected, can't mark new plays")
   }
 }
 
-#[derive(fmt::Debug, PartialEq, PartialOrd)]
+/// Error caused by a play that the rules don't allow
+#[derive(Debug, Clone, PartialEq)]
+pub enum IllegalPlayError {
+  /// The game has already been won
+  WinnerAlreadySelected,
+  /// The other player should be making the next play
+  NotPlayersTurn(Player),
+  /// Somebody has already marked the given spot
+  AlreadyMarked(Player),
+  /// A coordinate of the spot is beyond [`MIN_COORDINATE`] or
+  /// [`MAX_COORDINATE`]
+  OutOfBounds,
+}
+impl core::error::Error for IllegalPlayError {}
+impl fmt::Display for IllegalPlayError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      IllegalPlayError::WinnerAlreadySelected => {
+        write!(f, "Winner already selected, can't mark new plays")
+      }
+      IllegalPlayError::NotPlayersTurn(player) => write!(f, "It's {:?}'s turn to play", player),
+      IllegalPlayError::AlreadyMarked(player) => {
+        write!(f, "{:?} has already marked that spot", player)
+      }
+      IllegalPlayError::OutOfBounds => write!(
+        f,
+        "Coordinates have to be between {} and {}",
+        MIN_COORDINATE, MAX_COORDINATE
+      ),
+    }
+  }
+}
+impl From<WinnerAlreadySelectedError> for IllegalPlayError {
+  fn from(_: WinnerAlreadySelectedError) -> Self {
+    IllegalPlayError::WinnerAlreadySelected
+  }
+}
+
+#[derive(fmt::Debug, PartialEq, PartialOrd, Clone, Copy, Serialize, Deserialize)]
 pub struct Play {
   x: i128,
   y: i128,
   player: Player,
 }
 
+impl Play {
+  pub fn x(&self) -> i128 {
+    self.x
+  }
+
+  pub fn y(&self) -> i128 {
+    self.y
+  }
+
+  pub fn player(&self) -> Player {
+    self.player
+  }
+}
+
 /// The values selected stored in a two-layered binary tree map
 /// where the first layer has keys by X-coordinate and values are
 /// binary tree maps where keys are by Y-coordinate and value contains the player.
 ///
 /// For example: BTreeMap(100 => BTreeMap(50 => Player::Naught)) would mean that
 /// at location x:100 y=50, the Naught player had put a selection.
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 struct PlayedGames(BTreeMap<i128, BTreeMap<i128, Play>>);
 
 /// The length of a line that one needs to win the game
-const WINNING_LENGTH: i32 = 5;
+pub const WINNING_LENGTH: i32 = 5;
+
+/// The smallest coordinate a play can have. The rules look at the spots a
+/// few lines' lengths around each play, which have to fit in `i128` too.
+pub const MIN_COORDINATE: i128 = i128::MIN + 4 * WINNING_LENGTH as i128;
+/// The largest coordinate a play can have, see [`MIN_COORDINATE`]
+pub const MAX_COORDINATE: i128 = i128::MAX - 4 * WINNING_LENGTH as i128;
+
+fn within_bounds(&(x, y): &(i128, i128)) -> bool {
+  let bounds = MIN_COORDINATE..=MAX_COORDINATE;
+  bounds.contains(&x) && bounds.contains(&y)
+}
 
-impl<'a> PlayedGames {
+impl PlayedGames {
   pub fn mark(&mut self, player: Player, (x, y): (i128, i128)) {
-    let entry = self.0.entry(x).or_insert(BTreeMap::new());
+    let entry = self.0.entry(x).or_default();
     entry.insert(y, Play { player, x, y });
   }
 
@@ -70,7 +191,19 @@ impl<'a> PlayedGames {
   }
 
   pub fn longest_consecutive_line(&self, point: &(i128, i128)) -> Option<Vec<&Play>> {
-    let Play { x, y, player } = self.get(point)?;
+    let Play { player, .. } = self.get(point)?;
+    let longest_line = self.longest_line_through(point, *player);
+    Some(
+      longest_line
+        .iter()
+        .filter_map(|point| self.get(point))
+        .collect(),
+    )
+  }
+
+  /// The spots of the longest line of the player's plays through the given
+  /// spot, counting the spot as theirs whether it has been played or not
+  fn longest_line_through(&self, &(x, y): &(i128, i128), player: Player) -> Vec<(i128, i128)> {
     let mut possible_lines_of_five = vec![];
 
     let line_width_range = (-WINNING_LENGTH)..WINNING_LENGTH;
@@ -83,7 +216,7 @@ impl<'a> PlayedGames {
           let mut line_vec = vec![];
           for k in 0..j {
             // Horizontal plays: x grows, y stays the same
-            line_vec.push((x - i + k, *y));
+            line_vec.push((x - i + k, y));
           }
           possible_lines_of_five.push(line_vec);
         }
@@ -93,7 +226,7 @@ impl<'a> PlayedGames {
           let mut line_vec = vec![];
           for k in 0..j {
             // Vertical plays: x stays the same, y grows
-            line_vec.push((*x, y - i + k));
+            line_vec.push((x, y - i + k));
           }
           possible_lines_of_five.push(line_vec);
         }
@@ -120,28 +253,20 @@ impl<'a> PlayedGames {
       }
     }
 
-    let mut longest_line: Vec<&Play> = vec![];
+    let mut longest_line = vec![];
     // Go through all the possible lines we have generated
     for points in possible_lines_of_five {
-      // Get all the plays that have been played to a vector
-      let points_to_plays: Vec<&Play> = points.iter().filter_map(|point| self.get(point)).collect();
-      // If there were any blank spots, the line wasn't consecutive.
-      if points_to_plays.len() != points.len() {
-        continue;
-      }
-      // If we get here, there wasn't any blank spots in the line.
-      // Now all we need to check is that all plays are the same as the given play.
-      if points_to_plays
+      // If there were any blank spots or plays of the other player, the line
+      // wasn't consecutive.
+      let consecutive = points
         .iter()
-        .all(|line_play| line_play.player == *player)
-      {
-        // We found our line! Let's check if that's longest so far.
-        if points_to_plays.len() > longest_line.len() {
-          longest_line = points_to_plays;
-        }
+        .all(|point| *point == (x, y) || self.get(point).map(|play| play.player) == Some(player));
+      // We found our line! Let's check if that's longest so far.
+      if consecutive && points.len() > longest_line.len() {
+        longest_line = points;
       }
     }
-    Some(longest_line)
+    longest_line
   }
 }
 
@@ -151,7 +276,7 @@ impl GameArea {
     player: Player,
     x: i128,
     y: i128,
-  ) -> std::result::Result<(), WinnerAlreadySelectedError> {
+  ) -> core::result::Result<(), WinnerAlreadySelectedError> {
     if self.winner.is_some() {
       // There already is a winner, so we can't play.
       return Err(WinnerAlreadySelectedError);
@@ -182,6 +307,7 @@ impl GameArea {
     }
 
     self.games.mark(player, (x, y));
+    self.history.push((x, y));
 
     // Then calculate if the marked play resulted in a win.
     if let Some(longest_consecutive_line) = self.games.longest_consecutive_line(&(x, y)) {
@@ -193,21 +319,98 @@ impl GameArea {
     Ok(())
   }
 
+  /// Checks whether the given player is allowed to mark the given spot:
+  /// nobody has won yet, it is the player's turn and the spot is empty and
+  /// within [`MIN_COORDINATE`] and [`MAX_COORDINATE`].
+  ///
+  /// Unlike [`GameArea::mark`], this doesn't trust the caller to know the
+  /// rules, so it should be used for plays coming from the outside world.
+  pub fn validate_play(&self, player: Player, x: i128, y: i128) -> Result<(), IllegalPlayError> {
+    if self.winner.is_some() {
+      return Err(IllegalPlayError::WinnerAlreadySelected);
+    }
+    if let Some(next_player) = self.next_player() {
+      if next_player != player {
+        return Err(IllegalPlayError::NotPlayersTurn(next_player));
+      }
+    }
+    if !within_bounds(&(x, y)) {
+      return Err(IllegalPlayError::OutOfBounds);
+    }
+    if let Some(play) = self.games.get(&(x, y)) {
+      return Err(IllegalPlayError::AlreadyMarked(play.player));
+    }
+    Ok(())
+  }
+
+  /// Marks a new play after checking it with [`GameArea::validate_play`]
+  pub fn play(&mut self, player: Player, x: i128, y: i128) -> Result<(), IllegalPlayError> {
+    self.validate_play(player, x, y)?;
+    self.mark(player, x, y)?;
+    Ok(())
+  }
+
+  /// The player whose turn it is, or `None` if nobody has played yet and
+  /// either player may start.
+  pub fn next_player(&self) -> Option<Player> {
+    let last_play = self.history.last()?;
+    self.games.get(last_play).map(|play| !play.player)
+  }
+
   pub fn longest_consecutive_line(&self, x: i128, y: i128) -> Option<Vec<&Play>> {
     self.games.longest_consecutive_line(&(x, y))
   }
 
+  /// The empty spots where the given player would win right away, in the
+  /// order of their coordinates
+  pub fn winning_spots(&self, player: Player) -> Vec<(i128, i128)> {
+    if self.winner.is_some() {
+      return vec![];
+    }
+    // A winning spot extends a line, so it is next to one of the player's plays
+    let mut candidates = BTreeSet::new();
+    for play in self.moves().filter(|play| play.player == player) {
+      for dx in -1..=1 {
+        for dy in -1..=1 {
+          let spot = (play.x + dx, play.y + dy);
+          if within_bounds(&spot) && self.games.get(&spot).is_none() {
+            candidates.insert(spot);
+          }
+        }
+      }
+    }
+    // The lines through each candidate are checked as if it had been played,
+    // without copying the board
+    candidates
+      .into_iter()
+      .filter(|spot| self.games.longest_line_through(spot, player).len() >= WINNING_LENGTH as usize)
+      .collect()
+  }
+
+  /// Every play in the order they were marked
+  pub fn moves(&self) -> impl Iterator<Item = &Play> {
+    self
+      .history
+      .iter()
+      .filter_map(move |point| self.games.get(point))
+  }
+
   pub fn winner(&self) -> Option<Player> {
     self.winner
   }
 
+  /// The coordinates of the top left corner of the area, which is where the
+  /// first item of [`GameArea::all_plays`] is located.
+  pub fn origin(&self) -> (i128, i128) {
+    (self.left, self.top)
+  }
+
   pub fn width(&self) -> u128 {
-    (self.right - self.left).abs() as u128
+    self.right.abs_diff(self.left)
   }
 
-  #[allow(dead_code)]
   pub fn height(&self) -> u128 {
-    (self.bottom - self.top).abs() as u128
+    self.bottom.abs_diff(self.top)
   }
 
   pub fn all_plays(&self) -> Vec<Option<Player>> {
@@ -259,7 +462,9 @@ impl fmt::Display for GameArea {
 mod tests {
   use super::*;
   use proptest::prelude::*;
+  use rand::rngs::StdRng;
   use rand::seq::SliceRandom;
+  use rand::SeedableRng;
 
   /// Creates a new GameArea from a static template string
   ///
@@ -275,8 +480,11 @@ mod tests {
   ///    .x..",
   /// );
   /// ```
+  ///
+  /// The plays are marked in a shuffled order, which is the same on every
+  /// run so that a failing test fails again.
   fn create_area_from_template(template: &'static str) -> GameArea {
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(template.len() as u64);
     let lines: Vec<&str> = template.split("\n").collect();
     let height = lines.len() as i128;
     let width = lines[0].len() as i128;
@@ -793,6 +1001,111 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_play_follows_turn_order() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    assert_eq!(area.next_player(), None);
+    area.play(Player::Naught, 0, 0)?;
+    assert_eq!(area.next_player(), Some(Player::Cross));
+    assert_eq!(
+      area.play(Player::Naught, 1, 0),
+      Err(IllegalPlayError::NotPlayersTurn(Player::Cross))
+    );
+    area.play(Player::Cross, 1, 0)?;
+    assert_eq!(area.next_player(), Some(Player::Naught));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_play_rejects_marked_spot() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    area.play(Player::Cross, 0, 0)?;
+    assert_eq!(
+      area.play(Player::Naught, 0, 0),
+      Err(IllegalPlayError::AlreadyMarked(Player::Cross))
+    );
+    assert_area_formatted_to(
+      &area,
+      "⌜⎺⌝\n\
+       |x|\n\
+       ⌞⎽⌟",
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_play_rejects_plays_after_win() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, 1)?;
+    }
+    area.play(Player::Cross, 4, 0)?;
+    assert_eq!(area.winner(), Some(Player::Cross));
+    assert_eq!(
+      area.play(Player::Naught, 4, 1),
+      Err(IllegalPlayError::WinnerAlreadySelected)
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_play_rejects_spots_beyond_bounds() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for &(x, y) in &[
+      (i128::MAX, 0),
+      (0, i128::MIN),
+      (MAX_COORDINATE + 1, 0),
+      (0, MIN_COORDINATE - 1),
+    ] {
+      assert_eq!(
+        area.play(Player::Cross, x, y),
+        Err(IllegalPlayError::OutOfBounds)
+      );
+    }
+    assert_eq!(area.moves().count(), 0);
+
+    // Lines are still found at both ends of the range
+    for &(edge, step) in &[(MAX_COORDINATE, -1), (MIN_COORDINATE, 1)] {
+      let mut area = GameArea::default();
+      for i in 0..4 {
+        area.play(Player::Cross, edge + i * step, edge)?;
+        area.play(Player::Naught, edge + i * step, edge + step)?;
+      }
+      assert_eq!(
+        area.winning_spots(Player::Cross),
+        vec![(edge + 4 * step, edge)]
+      );
+      area.play(Player::Cross, edge + 4 * step, edge)?;
+      assert_eq!(area.winner(), Some(Player::Cross));
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_winning_spots() -> Result<(), IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, 2)?;
+    }
+    assert_eq!(area.winning_spots(Player::Cross), vec![(-1, 0), (4, 0)]);
+    assert_eq!(area.winning_spots(Player::Naught), vec![(-1, 2), (4, 2)]);
+
+    area.play(Player::Cross, 4, 0)?;
+    assert_eq!(area.winning_spots(Player::Naught), vec![]);
+
+    // Filling a gap in the middle of a line wins too
+    let area = GameArea::from_move_list("x0,0 o0,5 x1,1 o1,5 x3,3 o2,5 x4,4").unwrap();
+    assert_eq!(area.winning_spots(Player::Cross), vec![(2, 2)]);
+
+    Ok(())
+  }
+
   fn assert_line(actual_line: Vec<&Play>, expected_line: Vec<&Play>) {
     let mut expected_line = expected_line.clone();
     let mut actual_line = actual_line.clone();
@@ -0,0 +1,383 @@
+//! Text notations for positions, so that positions from bug reports and chat
+//! can be set up by pasting them.
+//!
+//! Three notations are understood:
+//!
+//! - Templates, which draw the board one row per line with `x` for crosses,
+//!   `o` for naughts and `.` for empty spots. The framed drawing produced by
+//!   formatting a [`GameArea`] with `{}` works too, with spaces as the empty
+//!   spots.
+//! - Move lists like `x0,0 o1,0 x0,1`, which are replayed in order.
+//! - The JSON interchange format of [`GameArea::to_json`].
+use super::{GameArea, IllegalPlayError, PlayedGames, Player, WINNING_LENGTH};
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// Error caused by trying to set up a position from text which isn't valid
+#[derive(Debug)]
+pub enum ParsePositionError {
+  /// There were no plays in the text
+  Empty,
+  /// A template had a character which is neither a play nor an empty spot
+  InvalidCharacter {
+    row: usize,
+    column: usize,
+    character: char,
+  },
+  /// A template row wasn't as wide as the first one
+  UnevenRow { row: usize },
+  /// A template had winning lines which couldn't all have been completed
+  /// with the last play of the game
+  SeveralWinningLines,
+  /// A move of a move list wasn't of the form `x0,0`
+  InvalidMove { index: usize },
+  /// A move of a move list wasn't allowed by the rules
+  IllegalMove {
+    index: usize,
+    error: IllegalPlayError,
+  },
+  #[cfg(feature = "std")]
+  Json(super::FromJsonError),
+}
+impl core::error::Error for ParsePositionError {}
+impl fmt::Display for ParsePositionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ParsePositionError::Empty => write!(f, "There are no plays in the position"),
+      ParsePositionError::InvalidCharacter {
+        row,
+        column,
+        character,
+      } => write!(
+        f,
+        "Invalid character '{}' on row {} column {}, expected 'x', 'o' or '.'",
+        character,
+        row + 1,
+        column + 1
+      ),
+      ParsePositionError::UnevenRow { row } => {
+        write!(f, "Row {} isn't as wide as the first row", row + 1)
+      }
+      ParsePositionError::SeveralWinningLines => write!(
+        f,
+        "The position has winning lines that the last play couldn't have all completed"
+      ),
+      ParsePositionError::InvalidMove { index } => write!(
+        f,
+        "Move number {} isn't of the form x0,0 or o0,0",
+        index + 1
+      ),
+      ParsePositionError::IllegalMove { index, error } => {
+        write!(f, "Illegal move number {}: {}", index + 1, error)
+      }
+      #[cfg(feature = "std")]
+      ParsePositionError::Json(error) => write!(f, "{}", error),
+    }
+  }
+}
+
+fn move_tokens(text: &str) -> impl Iterator<Item = &str> {
+  text
+    .split(|character: char| character.is_whitespace() || character == ';')
+    .filter(|token| !token.is_empty())
+}
+
+fn parse_move(token: &str) -> Option<(Player, i128, i128)> {
+  let mut characters = token.chars();
+  let player = match characters.next()? {
+    'x' | 'X' => Player::Cross,
+    'o' | 'O' => Player::Naught,
+    _ => return None,
+  };
+  let (x, y) = characters.as_str().split_once(',')?;
+  Some((player, x.parse().ok()?, y.parse().ok()?))
+}
+
+fn has_winning_line(plays: &[(Player, i128, i128)]) -> bool {
+  let mut board = PlayedGames::default();
+  for &(player, x, y) in plays {
+    board.mark(player, (x, y));
+  }
+  plays.iter().any(|&(_, x, y)| {
+    board
+      .longest_consecutive_line(&(x, y))
+      .is_some_and(|line| line.len() >= WINNING_LENGTH as usize)
+  })
+}
+
+/// Orders the plays of a template so that the position could have been
+/// reached by playing: players take turns as far as the counts of their plays
+/// allow, and a winning line is completed by the very last play.
+fn playing_order(
+  mut plays: Vec<(Player, i128, i128)>,
+) -> Result<Vec<(Player, i128, i128)>, ParsePositionError> {
+  let winning_play = if has_winning_line(&plays) {
+    let index = (0..plays.len())
+      .find(|&index| {
+        let mut others = plays.clone();
+        others.remove(index);
+        !has_winning_line(&others)
+      })
+      .ok_or(ParsePositionError::SeveralWinningLines)?;
+    Some(plays.remove(index))
+  } else {
+    None
+  };
+
+  let (mut crosses, mut naughts): (VecDeque<_>, VecDeque<_>) = plays
+    .into_iter()
+    .partition(|&(player, _, _)| player == Player::Cross);
+  // Whoever has more plays left started, and on a tie the winner plays last
+  let mut player = match crosses.len().cmp(&naughts.len()) {
+    core::cmp::Ordering::Greater => Player::Cross,
+    core::cmp::Ordering::Less => Player::Naught,
+    core::cmp::Ordering::Equal => winning_play.map_or(Player::Cross, |(winner, _, _)| !winner),
+  };
+
+  let mut ordered = Vec::new();
+  loop {
+    let (own, other) = match player {
+      Player::Cross => (&mut crosses, &mut naughts),
+      Player::Naught => (&mut naughts, &mut crosses),
+    };
+    match own.pop_front() {
+      Some(play) => ordered.push(play),
+      None => {
+        ordered.extend(other.drain(..));
+        break;
+      }
+    }
+    player = !player;
+  }
+  ordered.extend(winning_play);
+  Ok(ordered)
+}
+
+impl GameArea {
+  /// Sets up a position drawn as a template, see the module documentation.
+  ///
+  /// Templates don't tell the order of the plays, so they are marked in an
+  /// order which could have led to the position. The top left corner of the
+  /// template is at the origin.
+  pub fn from_template(template: &str) -> Result<GameArea, ParsePositionError> {
+    let lines: Vec<&str> = template
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .collect();
+    let framed = lines.first().is_some_and(|line| line.starts_with('⌜'));
+    let rows: Vec<&str> = if framed {
+      lines
+        .iter()
+        .filter(|line| !line.starts_with('⌜') && !line.starts_with('⌞'))
+        .map(|line| line.trim_start_matches('|').trim_end_matches('|'))
+        .collect()
+    } else {
+      lines
+    };
+
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    let mut plays = Vec::new();
+    for (row, line) in rows.iter().enumerate() {
+      if line.chars().count() != width {
+        return Err(ParsePositionError::UnevenRow { row });
+      }
+      for (column, character) in line.chars().enumerate() {
+        let player = match character {
+          '.' | ' ' => continue,
+          'x' => Player::Cross,
+          'o' => Player::Naught,
+          _ => {
+            return Err(ParsePositionError::InvalidCharacter {
+              row,
+              column,
+              character,
+            })
+          }
+        };
+        plays.push((player, column as i128, row as i128));
+      }
+    }
+    if plays.is_empty() {
+      return Err(ParsePositionError::Empty);
+    }
+
+    let mut area = GameArea::default();
+    for (player, x, y) in playing_order(plays)? {
+      area
+        .mark(player, x, y)
+        .map_err(|_| ParsePositionError::SeveralWinningLines)?;
+    }
+    Ok(area)
+  }
+
+  /// Replays a list of moves like `x0,0 o1,0 x0,1`. The moves are separated
+  /// by whitespace or semicolons, and they have to follow the rules.
+  pub fn from_move_list(moves: &str) -> Result<GameArea, ParsePositionError> {
+    let mut area = GameArea::default();
+    for (index, token) in move_tokens(moves).enumerate() {
+      let (player, x, y) = parse_move(token).ok_or(ParsePositionError::InvalidMove { index })?;
+      area
+        .play(player, x, y)
+        .map_err(|error| ParsePositionError::IllegalMove { index, error })?;
+    }
+    if area.moves().next().is_none() {
+      return Err(ParsePositionError::Empty);
+    }
+    Ok(area)
+  }
+
+  /// Draws the position as a template, see the module documentation
+  pub fn to_template(&self) -> String {
+    let width = self.width() as usize;
+    let mut template = String::new();
+    for (index, spot) in self.all_plays().iter().enumerate() {
+      if index > 0 && index % width == 0 {
+        template.push('\n');
+      }
+      template.push(match spot {
+        Some(Player::Cross) => 'x',
+        Some(Player::Naught) => 'o',
+        None => '.',
+      });
+    }
+    template
+  }
+
+  /// Lists the moves in the order they were played, like `x0,0 o1,0 x0,1`
+  pub fn to_move_list(&self) -> String {
+    let mut moves = String::new();
+    for play in self.moves() {
+      if !moves.is_empty() {
+        moves.push(' ');
+      }
+      let symbol = match play.player() {
+        Player::Cross => 'x',
+        Player::Naught => 'o',
+      };
+      // Writing into a string can't fail
+      let _ = write!(moves, "{}{},{}", symbol, play.x(), play.y());
+    }
+    moves
+  }
+
+  /// Sets up a position from text in any of the notations of the module
+  /// documentation, telling them apart by how the text starts.
+  pub fn parse_position(text: &str) -> Result<GameArea, ParsePositionError> {
+    let text = text.trim();
+    #[cfg(feature = "std")]
+    {
+      if text.starts_with('{') {
+        return GameArea::from_json(text).map_err(ParsePositionError::Json);
+      }
+    }
+    match move_tokens(text).next() {
+      Some(token) if parse_move(token).is_some() => GameArea::from_move_list(text),
+      _ => GameArea::from_template(text),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::format;
+
+  #[test]
+  fn test_template() {
+    let area = GameArea::parse_position(
+      "
+      .x..
+      .xo.
+      o...
+      ",
+    )
+    .unwrap();
+    assert_eq!(area.winner(), None);
+    assert_eq!(area.next_player(), Some(Player::Cross));
+    assert_eq!(
+      format!("{}", area),
+      "⌜⎺⎺⎺⌝\n\
+       | x |\n\
+       | xo|\n\
+       |o  |\n\
+       ⌞⎽⎽⎽⌟"
+    );
+  }
+
+  #[test]
+  fn test_formatted_area_round_trip() {
+    let area = GameArea::from_move_list("x0,0 o1,1 x2,0 o-1,3").unwrap();
+    let pasted = GameArea::parse_position(&format!("{}", area)).unwrap();
+    assert_eq!(format!("{}", pasted), format!("{}", area));
+    assert_eq!(pasted.check_consistency(), Ok(()));
+  }
+
+  #[test]
+  fn test_template_with_a_winner() {
+    // Only a cross in the middle of the line of six could have completed it
+    let area = GameArea::from_template(
+      "xxxxxx\n\
+       oo.ooo\n\
+       o.....",
+    )
+    .unwrap();
+    assert_eq!(area.winner(), Some(Player::Cross));
+    assert_eq!(area.check_consistency(), Ok(()));
+
+    assert!(matches!(
+      GameArea::from_template("xxxxx\nooooo"),
+      Err(ParsePositionError::SeveralWinningLines)
+    ));
+  }
+
+  #[test]
+  fn test_move_list() {
+    let area = GameArea::parse_position("x0,0; o-1,2\nx1,1").unwrap();
+    assert_eq!(area.moves().count(), 3);
+    assert_eq!(area.next_player(), Some(Player::Naught));
+
+    assert!(matches!(
+      GameArea::parse_position("x0,0 x1,1"),
+      Err(ParsePositionError::IllegalMove { index: 1, .. })
+    ));
+    assert!(matches!(
+      GameArea::parse_position("x0,0 o1"),
+      Err(ParsePositionError::InvalidMove { index: 1 })
+    ));
+  }
+
+  #[test]
+  fn test_copied_notations() {
+    let area = GameArea::from_move_list("x0,0 o1,1 x2,0 o-1,3").unwrap();
+    assert_eq!(area.to_template(), ".x.x\n..o.\n....\no...");
+    assert_eq!(area.to_move_list(), "x0,0 o1,1 x2,0 o-1,3");
+
+    let copied = GameArea::parse_position(&area.to_template()).unwrap();
+    assert_eq!(copied.to_template(), area.to_template());
+    let copied = GameArea::parse_position(&area.to_move_list()).unwrap();
+    assert_eq!(copied.to_move_list(), area.to_move_list());
+  }
+
+  #[test]
+  fn test_invalid_template() {
+    assert!(matches!(
+      GameArea::from_template("x.\n.y"),
+      Err(ParsePositionError::InvalidCharacter {
+        row: 1,
+        column: 1,
+        character: 'y'
+      })
+    ));
+    assert!(matches!(
+      GameArea::from_template("x.\n.o."),
+      Err(ParsePositionError::UnevenRow { row: 1 })
+    ));
+    assert!(matches!(
+      GameArea::parse_position(" \n "),
+      Err(ParsePositionError::Empty)
+    ));
+  }
+}
@@ -0,0 +1,108 @@
+//! Setting recorded games up again, for the formats games are saved and sent
+//! in.
+//!
+//! A recorded game is replayed play by play, so a game which breaks the rules
+//! is refused instead of being set up as is.
+use super::{GameArea, IllegalPlayError, Player, WINNING_LENGTH};
+use core::fmt;
+
+/// Error caused by a recorded game which doesn't hold up when replayed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+  /// The game was played with rules this version doesn't support
+  UnsupportedRules { winning_length: i32 },
+  /// One of the moves wasn't allowed by the rules
+  IllegalMove {
+    index: usize,
+    error: IllegalPlayError,
+  },
+  /// Replaying the moves ended in a different result than the one recorded
+  ResultMismatch,
+}
+impl core::error::Error for ReplayError {}
+impl fmt::Display for ReplayError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ReplayError::UnsupportedRules { winning_length } => write!(
+        f,
+        "Unsupported rules: winning length {} instead of {}",
+        winning_length, WINNING_LENGTH
+      ),
+      ReplayError::IllegalMove { index, error } => {
+        write!(f, "Illegal move number {}: {}", index + 1, error)
+      }
+      ReplayError::ResultMismatch => {
+        write!(f, "The recorded result doesn't match the moves")
+      }
+    }
+  }
+}
+
+impl GameArea {
+  /// Sets up a game played with the given winning length by replaying its
+  /// plays in order
+  pub fn replay<I>(winning_length: i32, plays: I) -> Result<GameArea, ReplayError>
+  where
+    I: IntoIterator<Item = (Player, i128, i128)>,
+  {
+    if winning_length != WINNING_LENGTH {
+      return Err(ReplayError::UnsupportedRules { winning_length });
+    }
+    let mut area = GameArea::default();
+    for (index, (player, x, y)) in plays.into_iter().enumerate() {
+      area
+        .play(player, x, y)
+        .map_err(|error| ReplayError::IllegalMove { index, error })?;
+    }
+    Ok(area)
+  }
+
+  /// Like [`GameArea::replay`], also checking that the game ended with the
+  /// recorded winner
+  pub fn replay_to_result<I>(
+    winning_length: i32,
+    plays: I,
+    winner: Option<Player>,
+  ) -> Result<GameArea, ReplayError>
+  where
+    I: IntoIterator<Item = (Player, i128, i128)>,
+  {
+    let area = GameArea::replay(winning_length, plays)?;
+    if area.winner() != winner {
+      return Err(ReplayError::ResultMismatch);
+    }
+    Ok(area)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::vec;
+
+  #[test]
+  fn test_replay() {
+    let plays = vec![(Player::Cross, 0, 0), (Player::Naught, 1, 0)];
+    let area = GameArea::replay(WINNING_LENGTH, plays.clone()).unwrap();
+    assert_eq!(area.moves().count(), 2);
+    assert_eq!(
+      GameArea::replay(3, plays.clone()).unwrap_err(),
+      ReplayError::UnsupportedRules { winning_length: 3 }
+    );
+    assert_eq!(
+      GameArea::replay_to_result(WINNING_LENGTH, plays, Some(Player::Cross)).unwrap_err(),
+      ReplayError::ResultMismatch
+    );
+    assert_eq!(
+      GameArea::replay(
+        WINNING_LENGTH,
+        vec![(Player::Cross, 0, 0), (Player::Cross, 1, 0)]
+      )
+      .unwrap_err(),
+      ReplayError::IllegalMove {
+        index: 1,
+        error: IllegalPlayError::NotPlayersTurn(Player::Naught)
+      }
+    );
+  }
+}
@@ -0,0 +1,72 @@
+//! Versions of the formats the game saves things in.
+//!
+//! Every format counts its own versions, starting from 1, and anything saved
+//! before a format had a version is read as version 1. Things saved in an
+//! older version are migrated when they are read, and things saved by newer
+//! versions of the game are refused with an [`UnsupportedVersionError`].
+use core::fmt;
+#[cfg(feature = "std")]
+use serde::Deserialize;
+
+/// Error caused by reading something saved by a newer version of the game
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnsupportedVersionError {
+  /// The version it was saved in
+  pub version: u32,
+  /// The newest version this version of the game reads
+  pub latest: u32,
+}
+impl core::error::Error for UnsupportedVersionError {}
+impl fmt::Display for UnsupportedVersionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Saved in format version {}, but this version of the game only reads up to {}",
+      self.version, self.latest
+    )
+  }
+}
+
+impl UnsupportedVersionError {
+  /// Refuses versions newer than `latest`
+  pub fn check(version: u32, latest: u32) -> Result<(), UnsupportedVersionError> {
+    if version > latest {
+      Err(UnsupportedVersionError { version, latest })
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Only the version of something saved, read before the rest so that
+/// newer formats are refused with a clear error instead of failing to parse
+#[cfg(feature = "std")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct VersionRecord {
+  #[serde(default = "first_version")]
+  pub version: u32,
+}
+
+/// The version of everything saved before its format had a version
+#[cfg(feature = "std")]
+pub(crate) fn first_version() -> u32 {
+  1
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_check() {
+    assert_eq!(UnsupportedVersionError::check(1, 2), Ok(()));
+    assert_eq!(UnsupportedVersionError::check(2, 2), Ok(()));
+    assert_eq!(
+      UnsupportedVersionError::check(3, 2),
+      Err(UnsupportedVersionError {
+        version: 3,
+        latest: 2
+      })
+    );
+  }
+}
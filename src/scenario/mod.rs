@@ -0,0 +1,143 @@
+//! Scenarios: positions the game can start from, like demos, tutorials and
+//! positions from bug reports.
+//!
+//! A scenario is a text file with the position in any of the notations of
+//! [`GameArea::parse_position`]. Lines starting with `#` describe the
+//! scenario:
+//!
+//! ```text
+//! # Crosses to play and win with a double threat
+//! ...x.
+//! .xx..
+//! ...o.
+//! ```
+//!
+//! The scenarios which come with the game are in the `scenarios` directory.
+use crate::rules::{GameArea, ParsePositionError};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The scenario the game starts from unless told otherwise
+pub const DEFAULT_SCENARIO: &str = include_str!("../../scenarios/example.txt");
+
+/// A position to start from, with its description
+#[derive(Debug, Clone)]
+pub struct Scenario {
+  pub description: String,
+  pub area: GameArea,
+}
+
+/// Error caused by loading a scenario
+#[derive(Debug)]
+pub enum ScenarioError {
+  Io(io::Error),
+  Position(ParsePositionError),
+}
+impl std::error::Error for ScenarioError {}
+impl fmt::Display for ScenarioError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ScenarioError::Io(error) => write!(f, "Couldn't read the scenario: {}", error),
+      ScenarioError::Position(error) => write!(f, "Invalid scenario: {}", error),
+    }
+  }
+}
+impl From<io::Error> for ScenarioError {
+  fn from(error: io::Error) -> Self {
+    ScenarioError::Io(error)
+  }
+}
+impl From<ParsePositionError> for ScenarioError {
+  fn from(error: ParsePositionError) -> Self {
+    ScenarioError::Position(error)
+  }
+}
+
+/// Where scenarios are looked up by name: the `FIRST_TO_FIVE_SCENARIOS`
+/// environment variable, or `scenarios` in the current directory
+pub fn directory() -> PathBuf {
+  env::var_os("FIRST_TO_FIVE_SCENARIOS")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from("scenarios"))
+}
+
+impl Scenario {
+  pub fn parse(text: &str) -> Result<Scenario, ParsePositionError> {
+    let (comments, position): (Vec<&str>, Vec<&str>) = text
+      .lines()
+      .partition(|line| line.trim_start().starts_with('#'));
+    let description = comments
+      .iter()
+      .map(|line| line.trim_start().trim_start_matches('#').trim())
+      .collect::<Vec<_>>()
+      .join(" ");
+    Ok(Scenario {
+      description,
+      area: GameArea::parse_position(&position.join("\n"))?,
+    })
+  }
+
+  pub fn load(path: &Path) -> Result<Scenario, ScenarioError> {
+    Ok(Scenario::parse(&fs::read_to_string(path)?)?)
+  }
+
+  /// Loads the scenario from the given file, or by its name from the
+  /// scenarios [`directory`], so that `open-four` is
+  /// `scenarios/open-four.txt`
+  pub fn find(name: &str) -> Result<Scenario, ScenarioError> {
+    let path = Path::new(name);
+    if path.is_file() {
+      return Scenario::load(path);
+    }
+    Scenario::load(&directory().join(name).with_extension("txt"))
+  }
+}
+
+impl Default for Scenario {
+  fn default() -> Scenario {
+    Scenario::parse(DEFAULT_SCENARIO).expect("The default scenario should be valid")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::Player;
+
+  #[test]
+  fn test_parse() {
+    let scenario = Scenario::parse(
+      "# Crosses to play
+       ...x.
+       # and win
+       .xx..
+       ...o.",
+    )
+    .unwrap();
+    assert_eq!(scenario.description, "Crosses to play and win");
+    assert_eq!(scenario.area.moves().count(), 4);
+  }
+
+  #[test]
+  fn test_scenarios_of_the_game() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+    for entry in fs::read_dir(&directory).unwrap() {
+      let path = entry.unwrap().path();
+      let scenario = Scenario::load(&path).unwrap_or_else(|error| panic!("{:?}: {}", path, error));
+      assert!(
+        !scenario.description.is_empty(),
+        "{:?} has no description",
+        path
+      );
+    }
+
+    assert_eq!(Scenario::default().area.winner(), Some(Player::Cross));
+    let double_threat = Scenario::load(&directory.join("double-threat.txt")).unwrap();
+    let mut area = double_threat.area;
+    area.play(Player::Cross, 3, 3).unwrap();
+    assert_eq!(area.winning_spots(Player::Cross).len(), 4);
+  }
+}
@@ -0,0 +1,301 @@
+//! An HTTP API for playing games, for scripts and dashboards which would
+//! rather not speak the WebSocket protocol.
+//!
+//! Games are kept in memory for as long as the server runs. The resources are:
+//!
+//! - `POST /games` creates a new game and responds with its id
+//! - `GET /games` lists every game, or only the finished ones with
+//!   `?status=finished`
+//! - `GET /games/:id` responds with the game in the JSON interchange format
+//!   of [`GameArea::to_json`]
+//! - `GET /games/:id/board` responds with the game area rendered as text,
+//!   when it is at most [`MAX_BOARD_SIDE`] spots wide and high
+//! - `POST /games/:id/moves` marks a play given as
+//!   `{ "player": "Cross", "x": 0, "y": 0 }`, with both coordinates between
+//!   `-MAX_COORDINATE` and [`MAX_COORDINATE`]
+use crate::rules::{GameArea, IllegalPlayError, Player};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::net::TcpListener;
+
+/// How far from the origin plays can be made through the API
+pub const MAX_COORDINATE: i128 = 1_000_000;
+
+/// How many spots wide and high a board can be to be rendered as text
+pub const MAX_BOARD_SIDE: u128 = 500;
+
+#[derive(Default)]
+struct Games {
+  next_id: u64,
+  games: BTreeMap<u64, GameArea>,
+}
+
+type SharedGames = Arc<Mutex<Games>>;
+
+/// Locks the games. A request which panicked while holding the lock may have
+/// left them half changed, so they aren't touched after that.
+fn lock(games: &SharedGames) -> Result<MutexGuard<'_, Games>, ApiError> {
+  games.lock().map_err(|_| ApiError::Poisoned)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CreatedGame {
+  id: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct GameSummary {
+  id: u64,
+  moves: usize,
+  winner: Option<Player>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StatusFilter {
+  InProgress,
+  Finished,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+  status: Option<StatusFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveRequest {
+  player: Player,
+  x: i128,
+  y: i128,
+}
+
+/// Error responses, with the reason in the body as `{ "error": "..." }`
+enum ApiError {
+  NotFound(u64),
+  IllegalMove(IllegalPlayError),
+  OutOfRange { x: i128, y: i128 },
+  TooLarge { width: u128, height: u128 },
+  Poisoned,
+}
+impl IntoResponse for ApiError {
+  fn into_response(self) -> Response {
+    let (status, message) = match self {
+      ApiError::NotFound(id) => (StatusCode::NOT_FOUND, format!("No game with id {}", id)),
+      ApiError::IllegalMove(error) => (StatusCode::CONFLICT, error.to_string()),
+      ApiError::OutOfRange { x, y } => (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        format!(
+          "The play {},{} is further than {} from the origin",
+          x, y, MAX_COORDINATE
+        ),
+      ),
+      ApiError::TooLarge { width, height } => (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        format!(
+          "The board is {}x{} spots, larger than the {}x{} rendered as text",
+          width, height, MAX_BOARD_SIDE, MAX_BOARD_SIDE
+        ),
+      ),
+      ApiError::Poisoned => (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "The games are unavailable after an earlier failure".to_string(),
+      ),
+    };
+    let body = serde_json::json!({ "error": message });
+    (status, Json(body)).into_response()
+  }
+}
+
+/// Builds the routes of the API, with an empty set of games
+pub fn router() -> Router {
+  Router::new()
+    .route("/games", get(list_games).post(create_game))
+    .route("/games/:id", get(get_game))
+    .route("/games/:id/board", get(get_board))
+    .route("/games/:id/moves", post(post_move))
+    .with_state(SharedGames::default())
+}
+
+/// Serves the API on the given listener until the server fails
+pub async fn serve(listener: TcpListener) -> io::Result<()> {
+  axum::serve(listener, router()).await
+}
+
+async fn create_game(
+  State(games): State<SharedGames>,
+) -> Result<(StatusCode, Json<CreatedGame>), ApiError> {
+  let mut games = lock(&games)?;
+  let id = games.next_id;
+  games.next_id += 1;
+  games.games.insert(id, GameArea::default());
+  Ok((StatusCode::CREATED, Json(CreatedGame { id })))
+}
+
+async fn list_games(
+  State(games): State<SharedGames>,
+  Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<GameSummary>>, ApiError> {
+  let games = lock(&games)?;
+  let summaries = games
+    .games
+    .iter()
+    .filter(|(_, area)| match query.status {
+      None => true,
+      Some(StatusFilter::InProgress) => area.winner().is_none(),
+      Some(StatusFilter::Finished) => area.winner().is_some(),
+    })
+    .map(|(&id, area)| GameSummary {
+      id,
+      moves: area.moves().count(),
+      winner: area.winner(),
+    })
+    .collect();
+  Ok(Json(summaries))
+}
+
+async fn get_game(
+  State(games): State<SharedGames>,
+  Path(id): Path<u64>,
+) -> Result<impl IntoResponse, ApiError> {
+  let games = lock(&games)?;
+  let area = games.games.get(&id).ok_or(ApiError::NotFound(id))?;
+  Ok(([(header::CONTENT_TYPE, "application/json")], area.to_json()))
+}
+
+async fn get_board(
+  State(games): State<SharedGames>,
+  Path(id): Path<u64>,
+) -> Result<String, ApiError> {
+  let games = lock(&games)?;
+  let area = games.games.get(&id).ok_or(ApiError::NotFound(id))?;
+  let (width, height) = (area.width(), area.height());
+  if width > MAX_BOARD_SIDE || height > MAX_BOARD_SIDE {
+    return Err(ApiError::TooLarge { width, height });
+  }
+  Ok(area.to_string())
+}
+
+async fn post_move(
+  State(games): State<SharedGames>,
+  Path(id): Path<u64>,
+  Json(play): Json<MoveRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+  let (x, y) = (play.x, play.y);
+  let range = -MAX_COORDINATE..=MAX_COORDINATE;
+  if !range.contains(&x) || !range.contains(&y) {
+    return Err(ApiError::OutOfRange { x, y });
+  }
+  let mut games = lock(&games)?;
+  let area = games.games.get_mut(&id).ok_or(ApiError::NotFound(id))?;
+  area
+    .play(play.player, x, y)
+    .map_err(ApiError::IllegalMove)?;
+  Ok(([(header::CONTENT_TYPE, "application/json")], area.to_json()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::body::Body;
+  use axum::http::Request;
+  use tower::ServiceExt;
+
+  async fn request(app: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, String) {
+    let request = Request::builder()
+      .method(method)
+      .uri(uri)
+      .header(header::CONTENT_TYPE, "application/json")
+      .body(Body::from(body.to_owned()))
+      .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+  }
+
+  #[tokio::test]
+  async fn test_play_a_game() {
+    let app = router();
+    let (status, body) = request(&app, "POST", "/games", "").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body, r#"{"id":0}"#);
+
+    for x in 0..4 {
+      let cross = format!(r#"{{ "player": "Cross", "x": {}, "y": 0 }}"#, x);
+      let naught = format!(r#"{{ "player": "Naught", "x": {}, "y": 1 }}"#, x);
+      assert_eq!(
+        request(&app, "POST", "/games/0/moves", &cross).await.0,
+        StatusCode::OK
+      );
+      assert_eq!(
+        request(&app, "POST", "/games/0/moves", &naught).await.0,
+        StatusCode::OK
+      );
+    }
+    let winning_move = r#"{ "player": "Cross", "x": 4, "y": 0 }"#;
+    let (status, body) = request(&app, "POST", "/games/0/moves", winning_move).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+      GameArea::from_json(&body).unwrap().winner(),
+      Some(Player::Cross)
+    );
+
+    let (_, body) = request(&app, "GET", "/games?status=finished", "").await;
+    let finished: Vec<GameSummary> = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+      finished,
+      vec![GameSummary {
+        id: 0,
+        moves: 9,
+        winner: Some(Player::Cross)
+      }]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_illegal_move_is_refused() {
+    let app = router();
+    request(&app, "POST", "/games", "").await;
+    let play = r#"{ "player": "Cross", "x": 0, "y": 0 }"#;
+    request(&app, "POST", "/games/0/moves", play).await;
+
+    let (status, body) = request(&app, "POST", "/games/0/moves", play).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body.contains("error"));
+  }
+
+  #[tokio::test]
+  async fn test_far_plays() {
+    let app = router();
+    request(&app, "POST", "/games", "").await;
+    let too_far = format!(r#"{{ "player": "Cross", "x": {}, "y": 0 }}"#, i128::MAX);
+    let (status, _) = request(&app, "POST", "/games/0/moves", &too_far).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    for (player, x) in &[("Cross", -MAX_COORDINATE), ("Naught", MAX_COORDINATE)] {
+      let play = format!(r#"{{ "player": "{}", "x": {}, "y": 0 }}"#, player, x);
+      let (status, _) = request(&app, "POST", "/games/0/moves", &play).await;
+      assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _) = request(&app, "GET", "/games/0/board", "").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    // The game is still there for the next requests
+    let (status, _) = request(&app, "GET", "/games/0", "").await;
+    assert_eq!(status, StatusCode::OK);
+  }
+
+  #[tokio::test]
+  async fn test_unknown_game() {
+    let (status, _) = request(&router(), "GET", "/games/42/board", "").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+  }
+}
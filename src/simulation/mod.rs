@@ -0,0 +1,296 @@
+//! Monte-Carlo playouts for assessing positions.
+//!
+//! A playout plays a position to the end with moves chosen by a [`Policy`],
+//! picking at random by default. Running thousands of them gives how often
+//! each player wins from the position, and running them after each
+//! candidate move gives how good the moves are.
+//!
+//! The board has no edges, so plays are only made close to the earlier
+//! ones, and a playout which goes on for too long is left unfinished.
+//!
+//! ```ignore
+//! let area = GameArea::from_move_list("x0,0 o1,1 x1,0")?;
+//! let outcomes = Simulation::default().assess(&area, &mut rand::thread_rng());
+//! println!("{:.0} %", outcomes.win_probability(Player::Cross) * 100.0);
+//! ```
+use crate::rules::{GameArea, Player};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+
+/// How many plays a playout makes at most before it is left unfinished
+pub const DEFAULT_MAX_PLAYS: usize = 100;
+
+/// How far from the earlier plays new plays are made, by default
+pub const DEFAULT_RADIUS: i128 = 1;
+
+/// Chooses the plays of a playout. Policies are shared by the threads
+/// running playouts with the `parallel` feature.
+pub trait Policy: Sync {
+  /// One of the candidate spots for the player to play. There is always at
+  /// least one candidate.
+  fn choose<R: Rng + ?Sized>(
+    &self,
+    area: &GameArea,
+    player: Player,
+    candidates: &[(i128, i128)],
+    rng: &mut R,
+  ) -> (i128, i128);
+}
+
+/// Plays anywhere near the earlier plays
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomPolicy;
+
+impl Policy for RandomPolicy {
+  fn choose<R: Rng + ?Sized>(
+    &self,
+    _area: &GameArea,
+    _player: Player,
+    candidates: &[(i128, i128)],
+    rng: &mut R,
+  ) -> (i128, i128) {
+    *candidates.choose(rng).expect("There should be candidates")
+  }
+}
+
+/// Wins when it can and blocks the opponent from winning when it has to,
+/// and otherwise plays at random. Playouts are slower, but closer to how
+/// people play.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreatPolicy;
+
+impl Policy for ThreatPolicy {
+  fn choose<R: Rng + ?Sized>(
+    &self,
+    area: &GameArea,
+    player: Player,
+    candidates: &[(i128, i128)],
+    rng: &mut R,
+  ) -> (i128, i128) {
+    let forced = area
+      .winning_spots(player)
+      .into_iter()
+      .chain(area.winning_spots(!player))
+      .next();
+    forced.unwrap_or_else(|| RandomPolicy.choose(area, player, candidates, rng))
+  }
+}
+
+/// How the playouts from a position ended
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Outcomes {
+  pub playouts: u32,
+  pub naught_wins: u32,
+  pub cross_wins: u32,
+}
+
+impl Outcomes {
+  fn record(&mut self, winner: Option<Player>) {
+    self.playouts += 1;
+    match winner {
+      Some(Player::Naught) => self.naught_wins += 1,
+      Some(Player::Cross) => self.cross_wins += 1,
+      None => {}
+    }
+  }
+
+  pub fn wins(&self, player: Player) -> u32 {
+    match player {
+      Player::Naught => self.naught_wins,
+      Player::Cross => self.cross_wins,
+    }
+  }
+
+  /// The playouts which reached the limit of plays without a winner
+  pub fn unfinished(&self) -> u32 {
+    self.playouts - self.naught_wins - self.cross_wins
+  }
+
+  /// The share of the playouts the player won, between 0 and 1
+  pub fn win_probability(&self, player: Player) -> f64 {
+    if self.playouts == 0 {
+      return 0.0;
+    }
+    f64::from(self.wins(player)) / f64::from(self.playouts)
+  }
+}
+
+/// How the playouts went after a candidate move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveOutcomes {
+  pub spot: (i128, i128),
+  pub outcomes: Outcomes,
+}
+
+/// The settings of a simulation
+#[derive(Debug, Clone)]
+pub struct Simulation<P = RandomPolicy> {
+  /// How many playouts are run from a position, or after each candidate move
+  pub playouts: u32,
+  pub max_plays: usize,
+  pub radius: i128,
+  pub policy: P,
+}
+
+impl Default for Simulation {
+  fn default() -> Simulation {
+    Simulation {
+      playouts: 1000,
+      max_plays: DEFAULT_MAX_PLAYS,
+      radius: DEFAULT_RADIUS,
+      policy: RandomPolicy,
+    }
+  }
+}
+
+/// The empty spots at most `radius` away from the given plays, or the origin
+/// on an empty board
+fn candidates(taken: &HashSet<(i128, i128)>, radius: i128) -> Vec<(i128, i128)> {
+  if taken.is_empty() {
+    return vec![(0, 0)];
+  }
+  let mut spots = HashSet::new();
+  for &(x, y) in taken {
+    spots.extend(neighbours((x, y), radius).filter(|spot| !taken.contains(spot)));
+  }
+  let mut spots: Vec<_> = spots.into_iter().collect();
+  // The order of a hash set changes from run to run, which would make
+  // seeded simulations differ
+  spots.sort_unstable();
+  spots
+}
+
+fn neighbours((x, y): (i128, i128), radius: i128) -> impl Iterator<Item = (i128, i128)> {
+  (-radius..=radius).flat_map(move |dx| (-radius..=radius).map(move |dy| (x + dx, y + dy)))
+}
+
+impl<P: Policy> Simulation<P> {
+  /// Plays the position to the end once, returning the winner
+  pub fn playout<R: Rng + ?Sized>(&self, area: &GameArea, rng: &mut R) -> Option<Player> {
+    let mut area = area.clone();
+    let mut taken: HashSet<_> = area.moves().map(|play| (play.x(), play.y())).collect();
+    let mut candidates = candidates(&taken, self.radius);
+    for _ in 0..self.max_plays {
+      if area.winner().is_some() || candidates.is_empty() {
+        break;
+      }
+      let player = area.next_player().unwrap_or(Player::Cross);
+      let spot = self.policy.choose(&area, player, &candidates, rng);
+      area
+        .mark(player, spot.0, spot.1)
+        .expect("Plays are only made until somebody wins");
+      taken.insert(spot);
+      candidates.retain(|&candidate| candidate != spot);
+      for neighbour in neighbours(spot, self.radius) {
+        if !taken.contains(&neighbour) && !candidates.contains(&neighbour) {
+          candidates.push(neighbour);
+        }
+      }
+    }
+    area.winner()
+  }
+
+  /// Runs the playouts from the position
+  pub fn assess<R: Rng + ?Sized>(&self, area: &GameArea, rng: &mut R) -> Outcomes {
+    let mut outcomes = Outcomes::default();
+    for _ in 0..self.playouts {
+      outcomes.record(self.playout(area, rng));
+    }
+    outcomes
+  }
+
+  /// Runs the playouts after each move the player in turn could make near
+  /// the earlier plays, the best moves for them first
+  pub fn assess_moves<R: Rng + ?Sized>(&self, area: &GameArea, rng: &mut R) -> Vec<MoveOutcomes> {
+    if area.winner().is_some() {
+      return vec![];
+    }
+    let player = area.next_player().unwrap_or(Player::Cross);
+    let taken = area.moves().map(|play| (play.x(), play.y())).collect();
+    // Each move gets a generator of its own, so that the moves can be
+    // assessed in any order and still give the same results for a seed
+    let seeded: Vec<_> = candidates(&taken, self.radius)
+      .into_iter()
+      .map(|spot| (spot, rng.gen::<u64>()))
+      .collect();
+    let assess_move = |(spot, seed): ((i128, i128), u64)| {
+      let mut after = area.clone();
+      after
+        .mark(player, spot.0, spot.1)
+        .expect("Nobody has won yet");
+      MoveOutcomes {
+        spot,
+        outcomes: self.assess(&after, &mut StdRng::seed_from_u64(seed)),
+      }
+    };
+    #[cfg(feature = "parallel")]
+    let mut moves: Vec<_> = {
+      use rayon::prelude::*;
+      seeded.into_par_iter().map(assess_move).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let mut moves: Vec<_> = seeded.into_iter().map(assess_move).collect();
+    moves.sort_by(|a, b| {
+      b.outcomes
+        .win_probability(player)
+        .total_cmp(&a.outcomes.win_probability(player))
+    });
+    moves
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_won_position() {
+    let area = GameArea::from_move_list("x0,0 o0,1 x1,0 o1,1 x2,0 o2,1 x3,0 o3,1 x4,0").unwrap();
+    let outcomes = Simulation::default().assess(&area, &mut StdRng::seed_from_u64(1));
+    assert_eq!(outcomes.win_probability(Player::Cross), 1.0);
+    assert!(Simulation::default()
+      .assess_moves(&area, &mut StdRng::seed_from_u64(1))
+      .is_empty());
+  }
+
+  #[test]
+  fn test_playouts() {
+    let area = GameArea::from_move_list("x0,0 o1,1").unwrap();
+    let simulation = Simulation {
+      playouts: 50,
+      ..Simulation::default()
+    };
+    let outcomes = simulation.assess(&area, &mut StdRng::seed_from_u64(1));
+    assert_eq!(outcomes.playouts, 50);
+    assert_eq!(
+      outcomes.naught_wins + outcomes.cross_wins + outcomes.unfinished(),
+      50
+    );
+    // The same seed plays the same games
+    assert_eq!(
+      simulation.assess(&area, &mut StdRng::seed_from_u64(1)),
+      outcomes
+    );
+  }
+
+  #[test]
+  fn test_best_move() {
+    // Cross wins at either end of the line, and otherwise naught blocks and
+    // wins with a line of its own
+    let area = GameArea::from_move_list("x0,0 o0,1 x1,0 o1,1 x2,0 o2,1 x3,0 o3,1").unwrap();
+    let simulation = Simulation {
+      playouts: 5,
+      max_plays: 10,
+      radius: 1,
+      policy: ThreatPolicy,
+    };
+    let moves = simulation.assess_moves(&area, &mut StdRng::seed_from_u64(1));
+    let best = moves[0];
+    assert!(matches!(best.spot, (-1, 0) | (4, 0)));
+    assert_eq!(best.outcomes.win_probability(Player::Cross), 1.0);
+    let worst = moves.last().unwrap();
+    assert_eq!(worst.outcomes.win_probability(Player::Naught), 1.0);
+  }
+}
@@ -0,0 +1,137 @@
+//! [proptest](https://docs.rs/proptest) strategies for generating games.
+//!
+//! Enable the `test-util` feature to use these in your own tests.
+use crate::rules::{GameArea, Player, WINNING_LENGTH};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::select;
+use std::ops::Range;
+
+/// The coordinates plays are generated within unless told otherwise. The
+/// range is small enough that lines form every now and then.
+pub const DEFAULT_COORDINATES: Range<i128> = -8..8;
+
+/// A player with either one starting the game
+pub fn player() -> impl Strategy<Value = Player> {
+  prop_oneof![Just(Player::Naught), Just(Player::Cross)]
+}
+
+/// A sequence of at most `max_len` plays within the given coordinates which
+/// the rules all allow: players take turns, no spot is marked twice and the
+/// sequence ends at the latest when somebody wins.
+pub fn play_sequence(
+  max_len: usize,
+  coordinates: Range<i128>,
+) -> impl Strategy<Value = Vec<(Player, i128, i128)>> {
+  let points = vec((coordinates.clone(), coordinates), 0..=max_len);
+  (player(), points).prop_map(|(first_player, points)| {
+    let mut area = GameArea::default();
+    let mut player = first_player;
+    let mut plays = vec![];
+    for (x, y) in points {
+      if area.winner().is_some() {
+        break;
+      }
+      // Spots which have already been marked are skipped
+      if area.play(player, x, y).is_ok() {
+        plays.push((player, x, y));
+        player = !player;
+      }
+    }
+    plays
+  })
+}
+
+/// A game area after playing a sequence from [`play_sequence`]. It may or may
+/// not have a winner.
+pub fn game_area(max_len: usize, coordinates: Range<i128>) -> impl Strategy<Value = GameArea> {
+  play_sequence(max_len, coordinates).prop_map(|plays| {
+    let mut area = GameArea::default();
+    for (player, x, y) in plays {
+      area
+        .play(player, x, y)
+        .expect("Generated plays should be valid");
+    }
+    area
+  })
+}
+
+/// A game one play away from being won
+#[derive(Debug, Clone)]
+pub struct NearWin {
+  pub area: GameArea,
+  /// The player who is about to win, and whose turn it is
+  pub player: Player,
+  /// The spot which wins the game for `player`
+  pub winning_play: (i128, i128),
+}
+
+/// A game where the player in turn has four plays of a line and the fifth
+/// spot, which can be at the end or in the middle of the line, is free. The
+/// opponent has played as many plays elsewhere.
+pub fn near_win(coordinates: Range<i128>) -> impl Strategy<Value = NearWin> {
+  let directions = select(vec![(1, 0), (0, 1), (1, 1), (1, -1)]);
+  let start = (coordinates.clone(), coordinates.clone());
+  let gap = 0..WINNING_LENGTH as i128;
+  let opponent_candidates = vec((coordinates.clone(), coordinates), 16);
+  (player(), directions, start, gap, opponent_candidates).prop_filter_map(
+    "Not enough free spots for the opponent",
+    |(player, (dx, dy), (start_x, start_y), gap, candidates)| {
+      let line: Vec<(i128, i128)> = (0..WINNING_LENGTH as i128)
+        .map(|i| (start_x + i * dx, start_y + i * dy))
+        .collect();
+      let winning_play = line[gap as usize];
+      let player_plays: Vec<(i128, i128)> = line
+        .iter()
+        .copied()
+        .filter(|&point| point != winning_play)
+        .collect();
+
+      let mut opponent_plays: Vec<(i128, i128)> = vec![];
+      for point in candidates {
+        if !line.contains(&point) && !opponent_plays.contains(&point) {
+          opponent_plays.push(point);
+        }
+      }
+      if opponent_plays.len() < player_plays.len() {
+        return None;
+      }
+
+      // Both make as many plays, so the player who starts is in turn at the end
+      let mut area = GameArea::default();
+      for (&(px, py), &(ox, oy)) in player_plays.iter().zip(&opponent_plays) {
+        area.play(player, px, py).ok()?;
+        area.play(!player, ox, oy).ok()?;
+      }
+      Some(NearWin {
+        area,
+        player,
+        winning_play,
+      })
+    },
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  proptest! {
+    #[test]
+    fn test_play_sequence_is_valid(plays in play_sequence(60, DEFAULT_COORDINATES)) {
+      let mut area = GameArea::default();
+      for (player, x, y) in plays {
+        prop_assert!(area.play(player, x, y).is_ok());
+      }
+    }
+
+    #[test]
+    fn test_near_win_wins(near_win in near_win(DEFAULT_COORDINATES)) {
+      let NearWin { mut area, player, winning_play: (x, y) } = near_win;
+      prop_assert_eq!(area.winner(), None);
+      prop_assert_eq!(area.next_player(), Some(player));
+      area.play(player, x, y).unwrap();
+      prop_assert_eq!(area.winner(), Some(player));
+    }
+  }
+}
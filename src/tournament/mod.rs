@@ -0,0 +1,492 @@
+//! Local tournaments, for club nights played on one computer.
+//!
+//! A tournament is either a round robin, where everybody plays everybody
+//! once, or a knockout, where the winner of each game goes on to the next
+//! round. The games are played one at a time in the order of
+//! [`Tournament::next_game`], and the tournament is stored in a TOML file in
+//! between so that it survives closing the game, along with the
+//! [`TOURNAMENT_FORMAT_VERSION`] it was written in.
+use crate::rules::{first_version, Player, UnsupportedVersionError, VersionRecord};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+  /// Everybody plays everybody once
+  RoundRobin,
+  /// Losers drop out until one player is left. When the number of players
+  /// isn't a power of two, the first players skip the first round.
+  Knockout,
+}
+
+/// A game between two players of the tournament
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pairing {
+  /// The round the game belongs to, starting from 1
+  pub round: u32,
+  pub naught: String,
+  pub cross: String,
+  /// Whether the game has been played and how it ended
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub result: Option<GameResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameResult {
+  NaughtWon,
+  CrossWon,
+  /// The game ended without a winner
+  Unfinished,
+}
+
+impl From<Option<Player>> for GameResult {
+  fn from(winner: Option<Player>) -> Self {
+    match winner {
+      Some(Player::Naught) => GameResult::NaughtWon,
+      Some(Player::Cross) => GameResult::CrossWon,
+      None => GameResult::Unfinished,
+    }
+  }
+}
+
+impl Pairing {
+  /// The name of the player who won the game, if it has been played and
+  /// somebody won
+  pub fn winner(&self) -> Option<&str> {
+    match self.result? {
+      GameResult::NaughtWon => Some(&self.naught),
+      GameResult::CrossWon => Some(&self.cross),
+      GameResult::Unfinished => None,
+    }
+  }
+}
+
+/// How a player is doing in the tournament
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+  pub name: String,
+  pub played: u32,
+  pub wins: u32,
+  pub losses: u32,
+  /// Games which ended without a winner
+  pub unfinished: u32,
+  /// A point for every win and half a point for every unfinished game
+  pub points: f64,
+}
+
+/// Error caused by setting up, storing or playing a tournament
+#[derive(Debug)]
+pub enum TournamentError {
+  Io(io::Error),
+  /// The tournament file isn't valid TOML in the expected shape
+  Parse(toml::de::Error),
+  Serialize(toml::ser::Error),
+  /// A tournament needs at least two players
+  TooFewPlayers,
+  /// There already is a player with the given name
+  DuplicateName(String),
+  /// A knockout game has to have a winner, so it has to be played again
+  NeedsWinner,
+  /// Every game of the tournament has been played
+  Finished,
+  /// The tournament was saved by a newer version of the game
+  Version(UnsupportedVersionError),
+}
+impl std::error::Error for TournamentError {}
+impl fmt::Display for TournamentError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TournamentError::Io(error) => write!(f, "Couldn't access the tournament: {}", error),
+      TournamentError::Parse(error) => write!(f, "Invalid tournament file: {}", error),
+      TournamentError::Serialize(error) => write!(f, "Couldn't save the tournament: {}", error),
+      TournamentError::TooFewPlayers => write!(f, "A tournament needs at least two players"),
+      TournamentError::DuplicateName(name) => write!(f, "{} is in the tournament twice", name),
+      TournamentError::NeedsWinner => {
+        write!(f, "A knockout game needs a winner, play the game again")
+      }
+      TournamentError::Finished => write!(f, "Every game of the tournament has been played"),
+      TournamentError::Version(error) => write!(f, "Invalid tournament file: {}", error),
+    }
+  }
+}
+impl From<io::Error> for TournamentError {
+  fn from(error: io::Error) -> Self {
+    TournamentError::Io(error)
+  }
+}
+
+/// The version of the format tournaments are saved in. Version 1 had no
+/// version field.
+pub const TOURNAMENT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+  #[serde(default = "first_version")]
+  version: u32,
+  pub format: Format,
+  /// The players in the order of their seeding
+  pub players: Vec<String>,
+  #[serde(default, rename = "pairing")]
+  pairings: Vec<Pairing>,
+}
+
+/// The `index`th game of a round between two players, alternating who plays
+/// crosses between games and rounds
+fn pairing(round: u32, index: usize, first: &str, second: &str) -> Pairing {
+  let (cross, naught) = if (round as usize + index) % 2 == 1 {
+    (first, second)
+  } else {
+    (second, first)
+  };
+  Pairing {
+    round,
+    naught: naught.to_owned(),
+    cross: cross.to_owned(),
+    result: None,
+  }
+}
+
+/// Pairs the first player with the last, the second with the second to
+/// last and so on
+fn pair_up(round: u32, players: &[&str]) -> Vec<Pairing> {
+  (0..players.len() / 2)
+    .map(|index| {
+      pairing(
+        round,
+        index,
+        players[index],
+        players[players.len() - 1 - index],
+      )
+    })
+    .collect()
+}
+
+/// Every round of a round robin with the circle method: the first player
+/// stays put and the others rotate around them. With an odd number of
+/// players, whoever is paired with the empty seat sits the round out.
+fn round_robin(players: &[String]) -> Vec<Pairing> {
+  let mut seats: Vec<Option<&str>> = players.iter().map(|name| Some(name.as_str())).collect();
+  if seats.len() % 2 == 1 {
+    seats.push(None);
+  }
+  let mut pairings = vec![];
+  for round in 1..seats.len() as u32 {
+    for index in 0..seats.len() / 2 {
+      if let (Some(first), Some(second)) = (seats[index], seats[seats.len() - 1 - index]) {
+        pairings.push(pairing(round, index, first, second));
+      }
+    }
+    seats[1..].rotate_right(1);
+  }
+  pairings
+}
+
+impl Tournament {
+  /// Sets up a tournament between the players, seeded in the given order,
+  /// and pairs the players for the first round
+  pub fn new(format: Format, players: Vec<String>) -> Result<Tournament, TournamentError> {
+    if players.len() < 2 {
+      return Err(TournamentError::TooFewPlayers);
+    }
+    for (index, name) in players.iter().enumerate() {
+      if players[..index].contains(name) {
+        return Err(TournamentError::DuplicateName(name.clone()));
+      }
+    }
+
+    let pairings = match format {
+      Format::RoundRobin => round_robin(&players),
+      Format::Knockout => {
+        // Enough players skip the first round to leave a power of two after
+        // it
+        let bracket = players.len().next_power_of_two();
+        let skipping = bracket - players.len();
+        let playing: Vec<&str> = players[skipping..].iter().map(String::as_str).collect();
+        pair_up(1, &playing)
+      }
+    };
+    Ok(Tournament {
+      version: TOURNAMENT_FORMAT_VERSION,
+      format,
+      players,
+      pairings,
+    })
+  }
+
+  /// Reads a tournament from the given file
+  pub fn load(path: &Path) -> Result<Tournament, TournamentError> {
+    let text = fs::read_to_string(path)?;
+    let VersionRecord { version } = toml::from_str(&text).map_err(TournamentError::Parse)?;
+    UnsupportedVersionError::check(version, TOURNAMENT_FORMAT_VERSION)
+      .map_err(TournamentError::Version)?;
+    let mut tournament: Tournament = toml::from_str(&text).map_err(TournamentError::Parse)?;
+    // There is only one version so far, so there is nothing to migrate
+    tournament.version = TOURNAMENT_FORMAT_VERSION;
+    Ok(tournament)
+  }
+
+  /// Writes the tournament to the given file
+  pub fn save(&self, path: &Path) -> Result<(), TournamentError> {
+    let text = toml::to_string_pretty(self).map_err(TournamentError::Serialize)?;
+    fs::write(path, text)?;
+    Ok(())
+  }
+
+  /// The games paired so far, played or not. Later knockout rounds are only
+  /// paired once the round before them has been played.
+  pub fn pairings(&self) -> &[Pairing] {
+    &self.pairings
+  }
+
+  /// The game to play next, or `None` when the tournament is over
+  pub fn next_game(&self) -> Option<&Pairing> {
+    self
+      .pairings
+      .iter()
+      .find(|pairing| pairing.result.is_none())
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.next_game().is_none()
+  }
+
+  /// Records the result of the game returned by [`Tournament::next_game`]
+  pub fn record_result(&mut self, winner: Option<Player>) -> Result<(), TournamentError> {
+    if self.format == Format::Knockout && winner.is_none() {
+      return Err(TournamentError::NeedsWinner);
+    }
+    let pairing = self
+      .pairings
+      .iter_mut()
+      .find(|pairing| pairing.result.is_none())
+      .ok_or(TournamentError::Finished)?;
+    pairing.result = Some(GameResult::from(winner));
+    let round = pairing.round;
+
+    if self.format == Format::Knockout && self.is_finished() {
+      let next_round = self.knockout_entrants(round);
+      if next_round.len() > 1 {
+        let pairings = pair_up(round + 1, &next_round);
+        self.pairings.extend(pairings);
+      }
+    }
+    Ok(())
+  }
+
+  /// The players going on to the round after the given knockout round: the
+  /// ones who skipped the first round, followed by the winners
+  fn knockout_entrants(&self, round: u32) -> Vec<&str> {
+    let mut entrants = vec![];
+    if round == 1 {
+      let skipping = self.players.len().next_power_of_two() - self.players.len();
+      entrants.extend(self.players[..skipping].iter().map(String::as_str));
+    }
+    entrants.extend(
+      self
+        .pairings
+        .iter()
+        .filter(|pairing| pairing.round == round)
+        .filter_map(Pairing::winner),
+    );
+    entrants
+  }
+
+  /// The winner of a finished knockout, or the leader of a round robin
+  pub fn winner(&self) -> Option<&str> {
+    if !self.is_finished() {
+      return None;
+    }
+    match self.format {
+      Format::Knockout => self.pairings.last().and_then(Pairing::winner),
+      Format::RoundRobin => {
+        let leader = self.standings().into_iter().next()?.name;
+        self
+          .players
+          .iter()
+          .find(|name| **name == leader)
+          .map(String::as_str)
+      }
+    }
+  }
+
+  /// The players ordered by their points, then by their wins and then by
+  /// their seeding
+  pub fn standings(&self) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = self
+      .players
+      .iter()
+      .map(|name| Standing {
+        name: name.clone(),
+        played: 0,
+        wins: 0,
+        losses: 0,
+        unfinished: 0,
+        points: 0.0,
+      })
+      .collect();
+    for pairing in &self.pairings {
+      let result = match pairing.result {
+        Some(result) => result,
+        None => continue,
+      };
+      for standing in standings.iter_mut() {
+        let player = if standing.name == pairing.naught {
+          Player::Naught
+        } else if standing.name == pairing.cross {
+          Player::Cross
+        } else {
+          continue;
+        };
+        standing.played += 1;
+        match (result, player) {
+          (GameResult::Unfinished, _) => {
+            standing.unfinished += 1;
+            standing.points += 0.5;
+          }
+          (GameResult::NaughtWon, Player::Naught) | (GameResult::CrossWon, Player::Cross) => {
+            standing.wins += 1;
+            standing.points += 1.0;
+          }
+          _ => standing.losses += 1,
+        }
+      }
+    }
+    // Sorting is stable, so ties keep the order of the seeding
+    standings.sort_by(|a, b| {
+      b.points
+        .partial_cmp(&a.points)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then(b.wins.cmp(&a.wins))
+    });
+    standings
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn players(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn test_round_robin_pairs_everybody_once() {
+    let names = players(&["Aino", "Eero", "Ville", "Saara", "Tuuli"]);
+    let tournament = Tournament::new(Format::RoundRobin, names.clone()).unwrap();
+    assert_eq!(tournament.pairings().len(), 10);
+    for (index, first) in names.iter().enumerate() {
+      for second in &names[index + 1..] {
+        let games = tournament
+          .pairings()
+          .iter()
+          .filter(|pairing| {
+            (&pairing.naught, &pairing.cross) == (first, second)
+              || (&pairing.naught, &pairing.cross) == (second, first)
+          })
+          .count();
+        assert_eq!(games, 1, "{} and {} should meet once", first, second);
+      }
+    }
+    // Nobody plays twice in the same round
+    for round in 1..=5 {
+      let mut seated: Vec<&String> = tournament
+        .pairings()
+        .iter()
+        .filter(|pairing| pairing.round == round)
+        .flat_map(|pairing| vec![&pairing.naught, &pairing.cross])
+        .collect();
+      let count = seated.len();
+      seated.sort();
+      seated.dedup();
+      assert_eq!(seated.len(), count);
+    }
+  }
+
+  #[test]
+  fn test_standings() {
+    let mut tournament =
+      Tournament::new(Format::RoundRobin, players(&["Aino", "Eero", "Ville"])).unwrap();
+    while let Some(pairing) = tournament.next_game() {
+      // Aino wins everything and the rest of the games end unfinished
+      let winner = if pairing.naught == "Aino" {
+        Some(Player::Naught)
+      } else if pairing.cross == "Aino" {
+        Some(Player::Cross)
+      } else {
+        None
+      };
+      tournament.record_result(winner).unwrap();
+    }
+    let standings = tournament.standings();
+    assert_eq!(standings[0].name, "Aino");
+    assert_eq!(standings[0].points, 2.0);
+    assert_eq!(standings[1].points, 0.5);
+    assert_eq!(standings[2].unfinished, 1);
+    assert_eq!(tournament.winner(), Some("Aino"));
+    assert!(matches!(
+      tournament.record_result(None),
+      Err(TournamentError::Finished)
+    ));
+  }
+
+  #[test]
+  fn test_knockout_with_byes() {
+    let mut tournament = Tournament::new(
+      Format::Knockout,
+      players(&["Aino", "Eero", "Ville", "Saara", "Tuuli"]),
+    )
+    .unwrap();
+    // Three players skip the first round to leave four for the semifinals
+    assert_eq!(tournament.pairings().len(), 1);
+    assert!(matches!(
+      tournament.record_result(None),
+      Err(TournamentError::NeedsWinner)
+    ));
+
+    // The naught player wins every game
+    while tournament.next_game().is_some() {
+      tournament.record_result(Some(Player::Naught)).unwrap();
+    }
+    let rounds: Vec<u32> = tournament
+      .pairings()
+      .iter()
+      .map(|pairing| pairing.round)
+      .collect();
+    assert_eq!(rounds, vec![1, 2, 2, 3]);
+    assert_eq!(
+      tournament.winner(),
+      Some(tournament.pairings()[3].naught.as_str())
+    );
+  }
+
+  #[test]
+  fn test_save_and_load() {
+    let path = std::env::temp_dir().join(format!(
+      "first-to-five-tournament-{}.toml",
+      std::process::id()
+    ));
+    let mut tournament = Tournament::new(Format::Knockout, players(&["Aino", "Eero"])).unwrap();
+    tournament.record_result(Some(Player::Cross)).unwrap();
+    tournament.save(&path).unwrap();
+
+    let loaded = Tournament::load(&path).unwrap();
+    assert_eq!(loaded, tournament);
+
+    fs::write(&path, "version = 99\n").unwrap();
+    let loaded = Tournament::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(
+      loaded,
+      Err(TournamentError::Version(UnsupportedVersionError {
+        version: 99,
+        ..
+      }))
+    ));
+  }
+}
@@ -0,0 +1,209 @@
+//! Export of finished games for embedding them on web pages.
+//!
+//! The viewer format is a self-contained JSON document described by the JSON
+//! schema in `docs/viewer.schema.json`. Unlike the interchange format of
+//! [`GameArea::to_json`], it is meant to be drawn rather than imported back:
+//! it carries the size of the board, the winning line and free-form metadata
+//! and comments for the moves.
+//!
+//! [`html_snippet`] wraps the document into HTML which a JavaScript board
+//! viewer can pick up from the page, and [`html_report`] makes a standalone
+//! page going through the game for sharing it without any viewer.
+use crate::rules::{GameArea, Play, Player};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+mod report;
+pub use report::html_report;
+
+/// The version of the viewer format produced by this build
+pub const VIEWER_FORMAT_VERSION: u32 = 1;
+
+/// Information about a game which the rules don't know about
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Metadata {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  /// Name of the player playing naughts
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub naught: Option<String>,
+  /// Name of the player playing crosses
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cross: Option<String>,
+  /// When the game was played, preferably as an ISO 8601 date
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub date: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ViewerDocument<'a> {
+  format: &'static str,
+  version: u32,
+  metadata: &'a Metadata,
+  board: Board,
+  moves: Vec<ViewerMove<'a>>,
+  result: ViewerResult,
+}
+
+#[derive(Serialize)]
+struct Board {
+  left: i128,
+  top: i128,
+  width: u128,
+  height: u128,
+}
+
+#[derive(Serialize)]
+struct ViewerMove<'a> {
+  x: i128,
+  y: i128,
+  player: Player,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  annotation: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum ViewerResult {
+  InProgress,
+  Won {
+    winner: Player,
+    line: Vec<(i128, i128)>,
+  },
+}
+
+/// Exports the game in the viewer format. Annotations are comments on moves,
+/// keyed by the index of the move they're about.
+pub fn to_viewer_json(
+  area: &GameArea,
+  metadata: &Metadata,
+  annotations: &BTreeMap<usize, String>,
+) -> String {
+  let moves = area
+    .moves()
+    .enumerate()
+    .map(|(index, play)| ViewerMove {
+      x: play.x(),
+      y: play.y(),
+      player: play.player(),
+      annotation: annotations.get(&index).map(String::as_str),
+    })
+    .collect();
+  let (left, top) = area.origin();
+  let document = ViewerDocument {
+    format: "first-to-five-viewer",
+    version: VIEWER_FORMAT_VERSION,
+    metadata,
+    board: Board {
+      left,
+      top,
+      width: area.width(),
+      height: area.height(),
+    },
+    moves,
+    result: result(area),
+  };
+  // Serializing plain structs to a string can't fail
+  serde_json::to_string(&document).unwrap()
+}
+
+fn result(area: &GameArea) -> ViewerResult {
+  let winner = match area.winner() {
+    None => return ViewerResult::InProgress,
+    Some(winner) => winner,
+  };
+  // The game ends on the play which completed the winning line
+  let line = area
+    .moves()
+    .last()
+    .and_then(|last| area.longest_consecutive_line(last.x(), last.y()))
+    .unwrap_or_default()
+    .into_iter()
+    .map(|play: &Play| (play.x(), play.y()))
+    .collect();
+  ViewerResult::Won { winner, line }
+}
+
+/// An HTML snippet embedding the game for the viewer script at the given URL.
+/// The script is expected to draw every `.first-to-five-game` element from
+/// the JSON inside it.
+pub fn html_snippet(
+  area: &GameArea,
+  metadata: &Metadata,
+  annotations: &BTreeMap<usize, String>,
+  viewer_script_url: &str,
+) -> String {
+  // "</" would end the script element early, and JSON allows escaping the
+  // slash
+  let json = to_viewer_json(area, metadata, annotations).replace("</", "<\\/");
+  format!(
+    concat!(
+      "<div class=\"first-to-five-game\">\n",
+      "  <script type=\"application/json\">{}</script>\n",
+      "</div>\n",
+      "<script src=\"{}\" defer></script>\n"
+    ),
+    json,
+    escape_attribute(viewer_script_url)
+  )
+}
+
+fn escape_attribute(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('"', "&quot;")
+    .replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::IllegalPlayError;
+
+  fn won_game() -> Result<GameArea, IllegalPlayError> {
+    let mut area = GameArea::default();
+    for x in 0..4 {
+      area.play(Player::Cross, x, 0)?;
+      area.play(Player::Naught, x, 1)?;
+    }
+    area.play(Player::Cross, 4, 0)?;
+    Ok(area)
+  }
+
+  #[test]
+  fn test_viewer_json() -> Result<(), IllegalPlayError> {
+    let metadata = Metadata {
+      title: Some(String::from("A quick one")),
+      ..Metadata::default()
+    };
+    let mut annotations = BTreeMap::new();
+    annotations.insert(8, String::from("Five in a row"));
+
+    let json = to_viewer_json(&won_game()?, &metadata, &annotations);
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json["format"], "first-to-five-viewer");
+    assert_eq!(
+      json["metadata"],
+      serde_json::json!({ "title": "A quick one" })
+    );
+    assert_eq!(json["moves"][0].get("annotation"), None);
+    assert_eq!(json["moves"][8]["annotation"], "Five in a row");
+    assert_eq!(json["result"]["winner"], "Cross");
+    assert_eq!(json["result"]["line"].as_array().unwrap().len(), 5);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_html_snippet_escapes_script_end() -> Result<(), IllegalPlayError> {
+    let metadata = Metadata {
+      title: Some(String::from("</script><b>")),
+      ..Metadata::default()
+    };
+    let html = html_snippet(&won_game()?, &metadata, &BTreeMap::new(), "viewer.js");
+    assert_eq!(html.matches("</script>").count(), 2);
+    assert!(html.contains("<script src=\"viewer.js\" defer></script>"));
+
+    Ok(())
+  }
+}
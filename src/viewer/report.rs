@@ -0,0 +1,338 @@
+//! Standalone HTML reports for going through a finished game afterwards.
+//!
+//! Unlike [`super::html_snippet`], a report needs no script: the key moments
+//! of the game are drawn as plain HTML tables, and the moves are annotated
+//! with the threats made and the wins missed or let through. Moves are also
+//! marked like in chess: `!` for making a double threat and `??` for a
+//! blunder.
+use super::Metadata;
+use crate::rules::{GameArea, Player};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::slice;
+
+const STYLE: &str = "
+  body { font-family: sans-serif; max-width: 50em; margin: 2em auto; }
+  table.board { border-collapse: collapse; margin: 1em 0; }
+  table.board td { width: 1.5em; height: 1.5em; border: 1px solid #ccc; text-align: center; }
+  table.board td.latest { background: #fe8; }
+  .naught { color: #1565c0; }
+  .cross { color: #c62828; }
+  .blunder { color: #c62828; font-weight: bold; }
+";
+
+/// What happened with a single move, as far as it can be told without
+/// looking further than one move ahead
+#[derive(Debug, Clone, PartialEq)]
+enum Remark {
+  /// The move made a threat to win with the next move at these spots
+  Threat(Vec<(i128, i128)>),
+  /// The player could have won at this spot, but played elsewhere
+  MissedWin((i128, i128)),
+  /// The opponent threatened to win at this spot and the move didn't block
+  /// it
+  MissedBlock((i128, i128)),
+}
+
+fn symbol(player: Player) -> &'static str {
+  match player {
+    Player::Naught => "o",
+    Player::Cross => "x",
+  }
+}
+
+fn class(player: Player) -> &'static str {
+  match player {
+    Player::Naught => "naught",
+    Player::Cross => "cross",
+  }
+}
+
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Replays the game and remarks on every move
+fn remarks(area: &GameArea) -> Vec<Vec<Remark>> {
+  let mut position = GameArea::default();
+  let mut remarks = vec![];
+  for play in area.moves() {
+    let (player, spot) = (play.player(), (play.x(), play.y()));
+    let own_spots = position.winning_spots(player);
+    let opponent_spots = position.winning_spots(!player);
+    // Rules are already checked by the game area being replayed
+    let _ = position.mark(player, spot.0, spot.1);
+
+    let mut move_remarks = vec![];
+    if position.winner() != Some(player) {
+      if let Some(&missed) = own_spots.first() {
+        move_remarks.push(Remark::MissedWin(missed));
+      }
+      // With more than one threat to block the damage was done earlier
+      if let [threat] = opponent_spots.as_slice() {
+        if *threat != spot {
+          move_remarks.push(Remark::MissedBlock(*threat));
+        }
+      }
+      let threats = position.winning_spots(player);
+      if !threats.is_empty() {
+        move_remarks.push(Remark::Threat(threats));
+      }
+    }
+    remarks.push(move_remarks);
+  }
+  remarks
+}
+
+/// The chess-like symbol for the quality of a move with these remarks. Only
+/// the moves which can be judged by looking one move ahead get a symbol.
+fn quality(remarks: &[Remark]) -> Option<&'static str> {
+  let blunder = remarks
+    .iter()
+    .any(|remark| matches!(remark, Remark::MissedWin(_) | Remark::MissedBlock(_)));
+  let double_threat = remarks
+    .iter()
+    .any(|remark| matches!(remark, Remark::Threat(threats) if threats.len() > 1));
+  if blunder {
+    Some("??")
+  } else if double_threat {
+    Some("!")
+  } else {
+    None
+  }
+}
+
+fn describe(remark: &Remark, player: Player) -> String {
+  let spots = |spots: &[(i128, i128)]| {
+    spots
+      .iter()
+      .map(|(x, y)| format!("{},{}", x, y))
+      .collect::<Vec<_>>()
+      .join(" and ")
+  };
+  match remark {
+    Remark::Threat(threats) if threats.len() > 1 => {
+      format!("Double threat: wins next at {}", spots(threats))
+    }
+    Remark::Threat(threats) => format!("Threatens to win at {}", spots(threats)),
+    Remark::MissedWin(spot) => format!("Blunder: could have won at {}", spots(&[*spot])),
+    Remark::MissedBlock(spot) => format!(
+      "Blunder: doesn't block {} at {}",
+      symbol(!player),
+      spots(&[*spot])
+    ),
+  }
+}
+
+/// How many spots the diagrams reach past the move and the spots its remarks
+/// are about
+const DIAGRAM_MARGIN: i128 = 2;
+
+/// The spots a remark is about
+fn remark_spots(remark: &Remark) -> &[(i128, i128)] {
+  match remark {
+    Remark::Threat(threats) => threats,
+    Remark::MissedWin(spot) | Remark::MissedBlock(spot) => slice::from_ref(spot),
+  }
+}
+
+/// Draws the board as it was after the move at `index`, around the move and
+/// the spots its remarks are about. The rest of the game is left out, so
+/// that diagrams of a sprawling game stay small.
+fn write_board(html: &mut String, area: &GameArea, index: usize, remarks: &[Remark]) {
+  let moves: Vec<_> = area.moves().take(index + 1).collect();
+  let latest = (moves[index].x(), moves[index].y());
+  let (mut left, mut top, mut right, mut bottom) = (latest.0, latest.1, latest.0, latest.1);
+  for &(x, y) in remarks.iter().flat_map(remark_spots) {
+    left = left.min(x);
+    top = top.min(y);
+    right = right.max(x);
+    bottom = bottom.max(y);
+  }
+  // Not past the plays of the whole game, where there is nothing to see
+  let (area_left, area_top) = area.origin();
+  let area_right = area_left + area.width() as i128 - 1;
+  let area_bottom = area_top + area.height() as i128 - 1;
+  let left = left.saturating_sub(DIAGRAM_MARGIN).max(area_left.min(left));
+  let top = top.saturating_sub(DIAGRAM_MARGIN).max(area_top.min(top));
+  let right = right
+    .saturating_add(DIAGRAM_MARGIN)
+    .min(area_right.max(right));
+  let bottom = bottom
+    .saturating_add(DIAGRAM_MARGIN)
+    .min(area_bottom.max(bottom));
+
+  html.push_str("<table class=\"board\">\n");
+  for y in top..=bottom {
+    html.push_str("  <tr>");
+    for x in left..=right {
+      let play = moves.iter().position(|play| (play.x(), play.y()) == (x, y));
+      match play {
+        Some(position) => {
+          let player = moves[position].player();
+          let latest = if position == index { " latest" } else { "" };
+          let _ = write!(
+            html,
+            "<td class=\"{}{}\">{}</td>",
+            class(player),
+            latest,
+            symbol(player)
+          );
+        }
+        None => html.push_str("<td></td>"),
+      }
+    }
+    html.push_str("</tr>\n");
+  }
+  html.push_str("</table>\n");
+}
+
+/// A standalone HTML page going through the game move by move. Annotations
+/// are comments on moves, keyed by the index of the move they're about, and
+/// they are shown next to the remarks made from the rules.
+///
+/// The board is drawn around every move which made a threat or a blunder,
+/// and around the last move.
+pub fn html_report(
+  area: &GameArea,
+  metadata: &Metadata,
+  annotations: &BTreeMap<usize, String>,
+) -> String {
+  let title = metadata
+    .title
+    .clone()
+    .unwrap_or_else(|| String::from("First to Five game"));
+  let name = |player: Player| {
+    let name = match player {
+      Player::Naught => &metadata.naught,
+      Player::Cross => &metadata.cross,
+    };
+    name.as_deref().map_or_else(
+      || String::from(symbol(player)),
+      |name| format!("{} ({})", escape_html(name), symbol(player)),
+    )
+  };
+
+  let mut html = String::new();
+  let _ = write!(
+    html,
+    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+    escape_html(&title),
+    STYLE,
+    escape_html(&title)
+  );
+  let _ = write!(
+    html,
+    "<p>{} against {}",
+    name(Player::Cross),
+    name(Player::Naught)
+  );
+  if let Some(date) = &metadata.date {
+    let _ = write!(html, ", {}", escape_html(date));
+  }
+  html.push_str("</p>\n");
+  let _ = match area.winner() {
+    Some(winner) => writeln!(html, "<p>{} won.</p>", name(winner)),
+    None => writeln!(html, "<p>Nobody won.</p>"),
+  };
+
+  let remarks = remarks(area);
+  let last_index = area.moves().count().saturating_sub(1);
+  html.push_str("<ol>\n");
+  for (index, play) in area.moves().enumerate() {
+    let player = play.player();
+    let _ = write!(
+      html,
+      "<li><span class=\"{}\">{}{},{}{}</span>",
+      class(player),
+      symbol(player),
+      play.x(),
+      play.y(),
+      quality(&remarks[index]).unwrap_or_default()
+    );
+    for remark in &remarks[index] {
+      let blunder = match remark {
+        Remark::Threat(_) => "",
+        Remark::MissedWin(_) | Remark::MissedBlock(_) => " class=\"blunder\"",
+      };
+      let _ = write!(
+        html,
+        " <span{}>{}</span>",
+        blunder,
+        describe(remark, player)
+      );
+    }
+    if let Some(annotation) = annotations.get(&index) {
+      let _ = write!(html, " <em>{}</em>", escape_html(annotation));
+    }
+    if !remarks[index].is_empty() || index == last_index {
+      html.push('\n');
+      write_board(&mut html, area, index, &remarks[index]);
+    }
+    html.push_str("</li>\n");
+  }
+  html.push_str("</ol>\n</body>\n</html>\n");
+  html
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_blunders_and_threats() {
+    // Naught doesn't block the four crosses, and cross then misses the win
+    let area =
+      GameArea::from_move_list("x0,0 o0,2 x1,0 o1,2 x2,0 o2,2 x3,0 o3,2 x5,5 o4,2").unwrap();
+    let remarks = remarks(&area);
+    assert_eq!(remarks[0], vec![]);
+    assert_eq!(remarks[6], vec![Remark::Threat(vec![(-1, 0), (4, 0)])]);
+    assert_eq!(
+      remarks[7],
+      vec![Remark::Threat(vec![(-1, 2), (4, 2)])],
+      "Cross has two threats, so not blocking them isn't blamed on this move"
+    );
+    assert_eq!(remarks[8][0], Remark::MissedWin((-1, 0)));
+
+    assert_eq!(quality(&remarks[0]), None);
+    assert_eq!(quality(&remarks[6]), Some("!"));
+    assert_eq!(quality(&remarks[8]), Some("??"));
+  }
+
+  #[test]
+  fn test_html_report() {
+    let area = GameArea::from_move_list("x0,0 o-1,0 x1,0 o0,1 x2,0 o1,1 x3,0 o5,5 x4,0").unwrap();
+    let metadata = Metadata {
+      title: Some(String::from("<Aino & Eero>")),
+      cross: Some(String::from("Aino")),
+      ..Metadata::default()
+    };
+    let mut annotations = BTreeMap::new();
+    annotations.insert(8, String::from("Five in a row"));
+
+    let html = html_report(&area, &metadata, &annotations);
+    assert!(html.contains("<h1>&lt;Aino &amp; Eero&gt;</h1>"));
+    assert!(html.contains("<p>Aino (x) won.</p>"));
+    assert!(html.contains("Blunder: doesn't block x at 4,0"));
+    assert!(html.contains("o5,5??</span>"));
+    assert!(html.contains("<em>Five in a row</em>"));
+    // Boards after the threat, the blunder and the last move
+    assert_eq!(html.matches("<table").count(), 3);
+  }
+
+  #[test]
+  fn test_diagrams_stay_near_the_move() {
+    let area = GameArea::from_move_list("x0,0 o-100,-100 x1,0").unwrap();
+    let mut html = String::new();
+    write_board(&mut html, &area, 2, &[]);
+    // Two spots past the move, but not past the plays of the game to the
+    // right of it and below it
+    assert_eq!(html.matches("<tr>").count(), 3);
+    assert_eq!(html.matches("<td").count(), 3 * 3);
+    assert!(html.contains("<td class=\"cross latest\">x</td>"));
+  }
+}
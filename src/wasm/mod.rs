@@ -0,0 +1,103 @@
+//! JavaScript bindings for running the rules in a browser.
+//!
+//! Build them with `wasm-pack build --target web -- --features wasm`.
+use crate::rules;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Player {
+  Naught,
+  Cross,
+}
+impl From<rules::Player> for Player {
+  fn from(player: rules::Player) -> Self {
+    match player {
+      rules::Player::Naught => Player::Naught,
+      rules::Player::Cross => Player::Cross,
+    }
+  }
+}
+impl From<Player> for rules::Player {
+  fn from(player: Player) -> Self {
+    match player {
+      Player::Naught => rules::Player::Naught,
+      Player::Cross => rules::Player::Cross,
+    }
+  }
+}
+
+#[wasm_bindgen(js_name = GameArea)]
+#[derive(Default)]
+pub struct JsGameArea {
+  area: rules::GameArea,
+}
+
+#[wasm_bindgen(js_class = GameArea)]
+impl JsGameArea {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> JsGameArea {
+    JsGameArea::default()
+  }
+
+  /// Marks a new play for the player, throwing an error if the rules don't
+  /// allow it.
+  pub fn mark(&mut self, player: Player, x: i32, y: i32) -> Result<(), JsValue> {
+    self
+      .play(player, x.into(), y.into())
+      .map_err(|error| JsValue::from_str(&error.to_string()))
+  }
+
+  /// Marks a play once the rules have checked it, including that the spot is
+  /// within the coordinate bounds
+  fn play(&mut self, player: Player, x: i128, y: i128) -> Result<(), rules::IllegalPlayError> {
+    self.area.play(player.into(), x, y)
+  }
+
+  pub fn winner(&self) -> Option<Player> {
+    self.area.winner().map(Player::from)
+  }
+
+  #[wasm_bindgen(js_name = nextPlayer)]
+  pub fn next_player(&self) -> Option<Player> {
+    self.area.next_player().map(Player::from)
+  }
+
+  /// The whole game in the JSON interchange format
+  #[wasm_bindgen(js_name = toJson)]
+  pub fn to_json(&self) -> String {
+    self.area.to_json()
+  }
+
+  /// Imports a game exported with `toJson`, throwing an error if it isn't
+  /// valid
+  #[wasm_bindgen(js_name = fromJson)]
+  pub fn from_json(json: &str) -> Result<JsGameArea, JsValue> {
+    rules::GameArea::from_json(json)
+      .map(|area| JsGameArea { area })
+      .map_err(|error| JsValue::from_str(&error.to_string()))
+  }
+
+  /// The game area drawn as text, like the GUI prints it
+  #[wasm_bindgen(js_name = toString)]
+  pub fn render(&self) -> String {
+    self.area.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_play_refuses_coordinates_beyond_bounds() {
+    let mut area = JsGameArea::new();
+    assert_eq!(
+      area.play(Player::Cross, i128::MAX, i128::MIN),
+      Err(rules::IllegalPlayError::OutOfBounds)
+    );
+    assert_eq!(area.next_player(), None);
+    assert_eq!(area.play(Player::Cross, i32::MIN.into(), 0), Ok(()));
+    assert_eq!(area.next_player(), Some(Player::Naught));
+  }
+}